@@ -0,0 +1,719 @@
+// コマンドライン引数の解析
+
+use std::path::PathBuf;
+
+use crate::compounding::{Compounding, RateBasis};
+use crate::contribution::{Adjustment, AgeTier, ContributionSchedule};
+use crate::error::Error;
+use crate::fees::{Fund, FundFees};
+use crate::ideco::{EmploymentCategory, IdecoConfig};
+use crate::inflation::Inflation;
+use crate::monte_carlo::{MonteCarloConfig, ReturnDistribution};
+use crate::nisa::NisaConfig;
+use crate::plan::{Plan, ValidPlan};
+use crate::taxable::CostBasisMethod;
+use crate::tvm::PaymentTiming;
+use crate::withdrawal::{WithdrawalConfig, WithdrawalStrategy};
+use crate::scenario;
+
+pub const HELP: &str = "\
+インデックス投資シミュレーター
+
+使い方:
+    hello-rust [オプション]
+
+オプション:
+    --scenario <ファイル> シナリオファイル (.toml / .json) から計画を読み込む
+                         (他のオプションで個別の値を上書きできます)
+    --target <金額>      目標資産額 (例: 100000000, 1億, 5000万)  [既定: 1億]
+    --rate <年利%>       想定年利をパーセントで指定 (例: 5, 4.5%)  [既定: 5]
+                         (-100%より大きく100%以下。0やマイナスも可)
+    --rate-basis <種類>  年利の種類。nominal (名目年利) または effective (実効年利)
+                                                                   [既定: nominal]
+    --compounding <頻度> 名目年利の複利の頻度。monthly / daily / annual / continuous
+                                                                   [既定: monthly]
+    --monthly <金額>     現在の毎月の投資額 (例: 50000, 5万)       [既定: 5万]
+    --initial <金額>     すでに保有している資産 (初期資産)          [既定: 0]
+    --timing <時期>      毎月の積立のタイミング。beginning (月初に積み立ててからその月を運用)
+                         または end (その月の運用の後に積立)    [既定: beginning]
+    --years <年数,...>   シミュレーションする期間をカンマ区切りで指定
+                         (例: 10,15,20)                            [既定: 10,15,20,25,30]
+    --age <歳>           現在の年齢 (iDeCoなどで使用)
+    --inflation <%,...>  インフレ率 (%)。カンマ区切りで年ごとに指定すると
+                         最後の率を以降の年にも使う (例: 3,2.5,2)
+                         指定すると目標額を今の価値とみなし、実質値も表示する
+
+積立額の推移 (いずれか1つ):
+    --step-rate <%>      毎年、積立額を前年の月額の % ずつ増やす (例: 3)
+    --step-amount <金額> 毎年、積立額を一定額ずつ増やす (例: 5000)
+    --tiers <歳:金額,...> 指定した年齢から積立額を変える (--age が必要)
+                         (例: 40:7万,50:10万)
+    --yearly-monthly <金額,...> 年ごとの月額を1年目から指定する
+                         (最後の月額を以降の年にも使う。例: 5万,5万,6万)
+    いずれの場合も、必要月額は同じ増やし方での1年目の月額を表示する
+
+月ごとの調整 (月は 年目-月 で指定。シミュレーションは1年目の1月に開始):
+    --bonus <月,...:金額> 毎年ボーナス月に上乗せする (例: 6,12:20万)
+    --deposit <年目-月:金額> 一度だけ上乗せする。複数回指定できる (例: 3-4:100万)
+    --pause <年目-月..年目-月> 積立を止める期間。複数回指定できる (例: 2-4..3-3)
+
+モンテカルロシミュレーション:
+    --monte-carlo        確率的なリターンでのシミュレーションも実行する
+                         (以下のいずれかを指定した場合も有効になります)
+    --mean <年利%>       期待年利 (%)                              [既定: --rate と同じ]
+    --volatility <%>     年率ボラティリティ (%)                    [既定: 15]
+    --paths <回数>       試行回数                                  [既定: 10000]
+    --seed <整数>        乱数シード (指定すると結果を再現できます)
+    --distribution <種類> normal または lognormal                  [既定: lognormal]
+    --confidence <%>     必要月額を逆算するときの目標到達確率 (%)  [既定: 90]
+
+税金:
+    --tax-rate <%>       課税口座の譲渡益にかかる税率 (%)          [既定: 20.315]
+    --cost-basis <方法>  取得価額の計算方法 average または fifo    [既定: average]
+
+手数料:
+    --expense-ratio <%>  信託報酬 (年率 %)。以下のいずれかを指定すると
+                         手数料を差し引いた資産額と手数料の総額を表示する
+    --front-load <%>     購入時手数料 (買付金額に対する %)         [既定: 0]
+    --retention-fee <%>  信託財産留保額 (売却額に対する %)         [既定: 0]
+    --fund <名前:信託報酬[:購入時手数料[:留保額]]>
+                         比較するファンド (%)。複数回指定すると同じ積立計画で
+                         最終資産と手数料の総額を比較する (例: eMAXIS:0.09)
+
+新NISA:
+    --nisa               つみたて投資枠・成長投資枠と生涯投資枠を考慮し、
+                         NISA口座と課税口座の内訳を表示する
+                         (以下のいずれかを指定した場合も有効になります)
+    --nisa-used <金額>   すでに使っている生涯投資枠 (簿価)         [既定: 0]
+    --no-growth-frame    成長投資枠を使わず、つみたて投資枠のみで積み立てる
+
+iDeCo (--age が必要):
+    --ideco <区分>       iDeCoの節税額と60歳以降の受取額を表示する
+                         区分: self-employed / employee / employee-with-pension /
+                               public-servant / dependent
+    --ideco-monthly <金額> 毎月の掛金                              [既定: 区分ごとの上限]
+    --taxable-income <金額> 課税所得 (節税額の計算に使用)           [既定: 400万]
+    --ideco-end-age <歳> 掛金を拠出する年齢の上限                  [既定: 60]
+    --payout-age <歳>    一時金で受け取る年齢                      [既定: 60]
+
+取り崩し:
+    --withdraw <金額>    積立期間 (--years の最長) の後、毎月引き出す額
+                         (以下のいずれかを指定した場合も有効になります)
+    --retirement-years <年> 取り崩す期間                            [既定: 30]
+    --retirement-rate <年利%> 取り崩し期間の想定年利               [既定: --rate と同じ]
+    --strategy <方法,...> 定額と比較する取り崩しの方法をカンマ区切りで指定
+                         four-percent (4%ルール、インフレ調整) / constant-percent (定率) /
+                         guardrails (Guyton-Klinger) / vpw
+                         年利固定とモンテカルロ (--volatility などの設定を使用) で比較する
+    --withdrawal-rate <%> 4%ルール・定率・ガードレールの引き出し率  [既定: 4]
+    --success-rate <%>   取り崩し期間に資産が尽きない割合がこの値以上になる、最大の引き出し率を
+                         年利固定・モンテカルロ・過去データ (--history) で求める (例: 95)
+
+過去データによる再現:
+    --history <CSV>      過去の指数データ (月次の価格またはリターン) で
+                         すべての開始月について積立を再現する
+
+    -h, --help           このヘルプを表示
+";
+
+pub enum Command {
+    Run(Box<ValidPlan>),
+    Help,
+}
+
+// コマンドラインで明示的に指定された値（シナリオファイルの値より優先する）
+#[derive(Default)]
+struct Overrides {
+    target_amount: Option<f64>,
+    annual_rate: Option<f64>,
+    current_monthly: Option<f64>,
+    initial_balance: Option<f64>,
+    timing: Option<PaymentTiming>,
+    rate_basis: Option<RateBasis>,
+    compounding: Option<Compounding>,
+    contribution: Option<ContributionSchedule>,
+    adjustments: Vec<Adjustment>,
+    periods: Option<Vec<usize>>,
+    monte_carlo: bool,
+    mean: Option<f64>,
+    volatility: Option<f64>,
+    paths: Option<usize>,
+    seed: Option<u64>,
+    distribution: Option<ReturnDistribution>,
+    confidence: Option<f64>,
+    history_file: Option<PathBuf>,
+    nisa: bool,
+    nisa_used: Option<f64>,
+    no_growth_frame: bool,
+    tax_rate: Option<f64>,
+    cost_basis: Option<CostBasisMethod>,
+    age: Option<u32>,
+    inflation: Option<Inflation>,
+    ideco_category: Option<EmploymentCategory>,
+    ideco_monthly: Option<f64>,
+    taxable_income: Option<f64>,
+    ideco_end_age: Option<u32>,
+    payout_age: Option<u32>,
+    expense_ratio: Option<f64>,
+    front_load: Option<f64>,
+    retention_fee: Option<f64>,
+    funds: Vec<Fund>,
+    withdraw: Option<f64>,
+    retirement_years: Option<usize>,
+    retirement_rate: Option<f64>,
+    strategies: Option<Vec<WithdrawalStrategy>>,
+    withdrawal_rate: Option<f64>,
+    success_rate: Option<f64>,
+}
+
+pub fn parse_args<I>(args: I) -> Result<Command, Error>
+where
+    I: IntoIterator<Item = String>,
+{
+    let Some((scenario_path, overrides)) = parse_flags(args).map_err(Error::Argument)? else {
+        return Ok(Command::Help);
+    };
+
+    let mut plan = match &scenario_path {
+        Some(path) => scenario::load(path)?,
+        None => Plan::default(),
+    };
+
+    if let Some(target_amount) = overrides.target_amount {
+        plan.target_amount = target_amount;
+    }
+    if let Some(annual_rate) = overrides.annual_rate {
+        plan.annual_rate = annual_rate;
+    }
+    if let Some(current_monthly) = overrides.current_monthly {
+        plan.current_monthly = current_monthly;
+    }
+    if let Some(initial_balance) = overrides.initial_balance {
+        plan.initial_balance = initial_balance;
+    }
+    if let Some(timing) = overrides.timing {
+        plan.contribution_timing = timing;
+    }
+    if let Some(basis) = overrides.rate_basis {
+        plan.rate_convention.basis = basis;
+    }
+    if let Some(compounding) = overrides.compounding {
+        plan.rate_convention.compounding = compounding;
+    }
+    if let Some(contribution) = overrides.contribution {
+        plan.contribution = contribution;
+    }
+    // 年ごとの月額は1年目の月額を兼ねるので、現在の月額と両方を指定すると一方が無視される
+    if overrides.current_monthly.is_some() && matches!(plan.contribution, ContributionSchedule::Yearly(_)) {
+        return Err(Error::Argument(
+            "--monthly は年ごとの月額 (--yearly-monthly またはシナリオファイルの yearly) と同時に指定できません".to_string(),
+        ));
+    }
+    plan.adjustments.extend(overrides.adjustments);
+    // 年ごとの月額を指定した場合は1年目の月額を現在の月額とする
+    if let ContributionSchedule::Yearly(amounts) = &plan.contribution
+        && let Some(&first) = amounts.first()
+    {
+        plan.current_monthly = first;
+    }
+    if let Some(periods) = overrides.periods {
+        plan.periods = periods;
+    }
+    if let Some(age) = overrides.age {
+        plan.age = Some(age);
+    }
+    if let Some(inflation) = overrides.inflation {
+        plan.inflation = Some(inflation);
+    }
+    if let Some(history_file) = overrides.history_file {
+        plan.history_file = Some(history_file);
+    }
+    if !overrides.funds.is_empty() {
+        plan.funds = overrides.funds;
+    }
+    if let Some(tax_rate) = overrides.tax_rate {
+        plan.tax.capital_gains_rate = tax_rate;
+    }
+    if let Some(cost_basis) = overrides.cost_basis {
+        plan.tax.method = cost_basis;
+    }
+
+    // モンテカルロ関連のオプションが1つでも指定されたら有効にする
+    let monte_carlo_requested = overrides.monte_carlo
+        || overrides.mean.is_some()
+        || overrides.volatility.is_some()
+        || overrides.paths.is_some()
+        || overrides.seed.is_some()
+        || overrides.distribution.is_some()
+        || overrides.confidence.is_some();
+    if monte_carlo_requested {
+        let config = plan.monte_carlo.get_or_insert_with(MonteCarloConfig::default);
+        if let Some(mean) = overrides.mean {
+            config.mean = Some(mean);
+        }
+        if let Some(volatility) = overrides.volatility {
+            config.volatility = volatility;
+        }
+        if let Some(paths) = overrides.paths {
+            config.paths = paths;
+        }
+        if let Some(seed) = overrides.seed {
+            config.seed = Some(seed);
+        }
+        if let Some(distribution) = overrides.distribution {
+            config.distribution = distribution;
+        }
+        if let Some(confidence) = overrides.confidence {
+            config.confidence = confidence;
+        }
+    }
+
+    // NISA関連のオプションが1つでも指定されたら有効にする
+    if overrides.nisa || overrides.nisa_used.is_some() || overrides.no_growth_frame {
+        let config = plan.nisa.get_or_insert_with(NisaConfig::default);
+        if let Some(used) = overrides.nisa_used {
+            config.used_lifetime = used;
+        }
+        if overrides.no_growth_frame {
+            config.use_growth_frame = false;
+        }
+    }
+
+    // iDeCo関連のオプションが1つでも指定されたら有効にする
+    let ideco_requested = overrides.ideco_category.is_some()
+        || overrides.ideco_monthly.is_some()
+        || overrides.taxable_income.is_some()
+        || overrides.ideco_end_age.is_some()
+        || overrides.payout_age.is_some();
+    if ideco_requested {
+        let config = plan.ideco.get_or_insert_with(IdecoConfig::default);
+        if let Some(category) = overrides.ideco_category {
+            config.category = category;
+        }
+        if let Some(monthly) = overrides.ideco_monthly {
+            config.monthly_contribution = Some(monthly);
+        }
+        if let Some(income) = overrides.taxable_income {
+            config.taxable_income = income;
+        }
+        if let Some(age) = overrides.ideco_end_age {
+            config.contribution_end_age = age;
+        }
+        if let Some(age) = overrides.payout_age {
+            config.payout_age = age;
+        }
+    }
+
+    // 手数料関連のオプションが1つでも指定されたら有効にする
+    if overrides.expense_ratio.is_some() || overrides.front_load.is_some() || overrides.retention_fee.is_some() {
+        let fees = plan.fees.get_or_insert_with(FundFees::default);
+        if let Some(rate) = overrides.expense_ratio {
+            fees.expense_ratio = rate;
+        }
+        if let Some(rate) = overrides.front_load {
+            fees.front_load = rate;
+        }
+        if let Some(rate) = overrides.retention_fee {
+            fees.retention_fee = rate;
+        }
+    }
+
+    // 取り崩し関連のオプションが1つでも指定されたら有効にする
+    let withdrawal_requested = overrides.withdraw.is_some()
+        || overrides.retirement_years.is_some()
+        || overrides.retirement_rate.is_some()
+        || overrides.strategies.is_some()
+        || overrides.withdrawal_rate.is_some()
+        || overrides.success_rate.is_some();
+    if withdrawal_requested {
+        let config = plan.withdrawal.get_or_insert_with(WithdrawalConfig::default);
+        if let Some(amount) = overrides.withdraw {
+            config.monthly_withdrawal = amount;
+        }
+        if let Some(years) = overrides.retirement_years {
+            config.years = years;
+        }
+        if let Some(rate) = overrides.retirement_rate {
+            config.annual_rate = Some(rate);
+        }
+        if let Some(strategies) = overrides.strategies {
+            config.strategies = strategies;
+        }
+        if let Some(rate) = overrides.withdrawal_rate {
+            config.initial_rate = rate;
+        }
+        if let Some(rate) = overrides.success_rate {
+            config.success_rate = Some(rate);
+        }
+    }
+
+    // シナリオファイルを使った場合は、値の誤りにファイル名を添える
+    let plan = plan.into_valid().map_err(|error| Error::InvalidPlan {
+        error,
+        scenario: scenario_path,
+    })?;
+    Ok(Command::Run(Box::new(plan)))
+}
+
+// オプションを読み取る（--help が指定された場合は None）
+fn parse_flags<I>(args: I) -> Result<Option<(Option<PathBuf>, Overrides)>, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut scenario_path: Option<PathBuf> = None;
+    let mut overrides = Overrides::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        }
+
+        // `--flag=value` と `--flag value` の両方を受け付ける
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} には値が必要です", flag))
+        };
+        // 値を取らないオプション（`--nisa=false` などは受け付けない）
+        let switch = || match &inline_value {
+            Some(_) => Err(format!("{} は値を取りません", flag)),
+            None => Ok(true),
+        };
+
+        match flag.as_str() {
+            "--scenario" => scenario_path = Some(PathBuf::from(value()?)),
+            "--target" => overrides.target_amount = Some(parse_flag_amount("--target", &value()?)?),
+            "--rate" => overrides.annual_rate = Some(parse_rate("--rate", &value()?)?),
+            "--monthly" => overrides.current_monthly = Some(parse_flag_amount("--monthly", &value()?)?),
+            "--initial" => overrides.initial_balance = Some(parse_flag_amount("--initial", &value()?)?),
+            "--rate-basis" => {
+                overrides.rate_basis = Some(RateBasis::parse(&value()?).map_err(|e| format!("--rate-basis: {}", e))?)
+            }
+            "--compounding" => {
+                overrides.compounding = Some(Compounding::parse(&value()?).map_err(|e| format!("--compounding: {}", e))?)
+            }
+            "--timing" => {
+                overrides.timing = Some(PaymentTiming::parse(&value()?).map_err(|e| format!("--timing: {}", e))?)
+            }
+            "--step-rate" => {
+                let rate = parse_rate("--step-rate", &value()?)?;
+                set_contribution(&mut overrides, ContributionSchedule::PercentStep(rate))?;
+            }
+            "--step-amount" => {
+                let amount = parse_flag_amount("--step-amount", &value()?)?;
+                set_contribution(&mut overrides, ContributionSchedule::AmountStep(amount))?;
+            }
+            "--tiers" => {
+                let tiers = parse_tiers("--tiers", &value()?)?;
+                set_contribution(&mut overrides, ContributionSchedule::AgeTiers(tiers))?;
+            }
+            "--yearly-monthly" => {
+                let amounts = value()?
+                    .split(',')
+                    .map(|part| parse_flag_amount("--yearly-monthly", part))
+                    .collect::<Result<Vec<_>, _>>()?;
+                set_contribution(&mut overrides, ContributionSchedule::Yearly(amounts))?;
+            }
+            "--bonus" => overrides.adjustments.push(parse_bonus("--bonus", &value()?)?),
+            "--deposit" => overrides.adjustments.push(parse_deposit("--deposit", &value()?)?),
+            "--pause" => overrides.adjustments.push(parse_pause("--pause", &value()?)?),
+            "--years" => overrides.periods = Some(parse_years("--years", &value()?)?),
+            "--monte-carlo" => overrides.monte_carlo = switch()?,
+            "--mean" => overrides.mean = Some(parse_rate("--mean", &value()?)?),
+            "--volatility" => overrides.volatility = Some(parse_rate("--volatility", &value()?)?),
+            "--paths" => overrides.paths = Some(parse_integer("--paths", &value()?)?),
+            "--seed" => overrides.seed = Some(parse_integer("--seed", &value()?)?),
+            "--distribution" => {
+                overrides.distribution = Some(
+                    ReturnDistribution::parse(&value()?).map_err(|e| format!("--distribution: {}", e))?,
+                )
+            }
+            "--confidence" => overrides.confidence = Some(parse_rate("--confidence", &value()?)?),
+            "--inflation" => overrides.inflation = Some(parse_inflation("--inflation", &value()?)?),
+            "--age" => overrides.age = Some(parse_integer("--age", &value()?)?),
+            "--ideco" => {
+                overrides.ideco_category = Some(
+                    EmploymentCategory::parse(&value()?).map_err(|e| format!("--ideco: {}", e))?,
+                )
+            }
+            "--ideco-monthly" => overrides.ideco_monthly = Some(parse_flag_amount("--ideco-monthly", &value()?)?),
+            "--taxable-income" => overrides.taxable_income = Some(parse_flag_amount("--taxable-income", &value()?)?),
+            "--ideco-end-age" => overrides.ideco_end_age = Some(parse_integer("--ideco-end-age", &value()?)?),
+            "--payout-age" => overrides.payout_age = Some(parse_integer("--payout-age", &value()?)?),
+            "--history" => overrides.history_file = Some(PathBuf::from(value()?)),
+            "--nisa" => overrides.nisa = switch()?,
+            "--nisa-used" => overrides.nisa_used = Some(parse_flag_amount("--nisa-used", &value()?)?),
+            "--no-growth-frame" => overrides.no_growth_frame = switch()?,
+            "--expense-ratio" => overrides.expense_ratio = Some(parse_rate("--expense-ratio", &value()?)?),
+            "--front-load" => overrides.front_load = Some(parse_rate("--front-load", &value()?)?),
+            "--retention-fee" => overrides.retention_fee = Some(parse_rate("--retention-fee", &value()?)?),
+            "--fund" => overrides.funds.push(parse_fund("--fund", &value()?)?),
+            "--withdraw" => overrides.withdraw = Some(parse_flag_amount("--withdraw", &value()?)?),
+            "--retirement-years" => overrides.retirement_years = Some(parse_integer("--retirement-years", &value()?)?),
+            "--retirement-rate" => overrides.retirement_rate = Some(parse_rate("--retirement-rate", &value()?)?),
+            "--strategy" => {
+                overrides.strategies = Some(
+                    value()?
+                        .split(',')
+                        .map(|part| WithdrawalStrategy::parse(part).map_err(|e| format!("--strategy: {}", e)))
+                        .collect::<Result<Vec<_>, _>>()?,
+                )
+            }
+            "--withdrawal-rate" => overrides.withdrawal_rate = Some(parse_rate("--withdrawal-rate", &value()?)?),
+            "--success-rate" => overrides.success_rate = Some(parse_rate("--success-rate", &value()?)?),
+            "--tax-rate" => overrides.tax_rate = Some(parse_rate("--tax-rate", &value()?)?),
+            "--cost-basis" => {
+                overrides.cost_basis = Some(
+                    CostBasisMethod::parse(&value()?).map_err(|e| format!("--cost-basis: {}", e))?,
+                )
+            }
+            _ => return Err(format!("不明なオプションです: {}", flag)),
+        }
+    }
+
+    Ok(Some((scenario_path, overrides)))
+}
+
+// 金額の解析: 「万」「億」の単位を受け付ける
+pub fn parse_amount(value: &str) -> Result<f64, String> {
+    let value = value.trim().trim_end_matches('円').replace([',', '_'], "");
+    let (number, unit) = if let Some(number) = value.strip_suffix('億') {
+        (number, 100_000_000.0)
+    } else if let Some(number) = value.strip_suffix('万') {
+        (number, 10_000.0)
+    } else {
+        (value.as_str(), 1.0)
+    };
+
+    let amount: f64 = number
+        .parse()
+        .map_err(|_| format!("金額を解釈できません: {}", value))?;
+    if !amount.is_finite() {
+        return Err(format!("金額が不正です: {}", value));
+    }
+    Ok(amount * unit)
+}
+
+fn parse_flag_amount(name: &str, value: &str) -> Result<f64, String> {
+    parse_amount(value).map_err(|e| format!("{}: {}", name, e))
+}
+
+// 年利の解析: パーセントで指定する (5 → 0.05)
+pub fn parse_rate(name: &str, value: &str) -> Result<f64, String> {
+    let number = value.trim().trim_end_matches('%');
+    let percent: f64 = number
+        .parse()
+        .map_err(|_| format!("{} の値を解釈できません: {}", name, value))?;
+    if !percent.is_finite() {
+        return Err(format!("{} の値が不正です: {}", name, value));
+    }
+    Ok(percent / 100.0)
+}
+
+// インフレ率の解析: 1つなら一定、カンマ区切りなら年ごとの率
+fn parse_inflation(name: &str, value: &str) -> Result<Inflation, String> {
+    let rates = value
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| parse_rate(name, part))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match rates.as_slice() {
+        [] => Err(format!("{} には少なくとも1つの率を指定してください", name)),
+        [rate] => Ok(Inflation::constant(*rate)),
+        _ => Ok(Inflation::schedule(rates)),
+    }
+}
+
+// 積立額の推移は1つだけ指定できる
+fn set_contribution(overrides: &mut Overrides, schedule: ContributionSchedule) -> Result<(), String> {
+    if overrides.contribution.is_some() {
+        return Err("--step-rate / --step-amount / --tiers / --yearly-monthly はいずれか1つを指定してください".to_string());
+    }
+    overrides.contribution = Some(schedule);
+    Ok(())
+}
+
+// 年齢別の積立額の解析: 歳:金額 のカンマ区切り
+fn parse_tiers(name: &str, value: &str) -> Result<Vec<AgeTier>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (age, monthly) = part
+                .split_once(':')
+                .ok_or_else(|| format!("{} は 歳:金額 の形式で指定してください: {}", name, part))?;
+            Ok(AgeTier {
+                age: parse_integer(name, age)?,
+                monthly: parse_flag_amount(name, monthly)?,
+            })
+        })
+        .collect()
+}
+
+// シミュレーション上の月の解析: 年目-月 (例: 3-4 は3年目の4月) → 1始まりの経過月
+pub fn parse_plan_month(value: &str) -> Result<usize, String> {
+    let (year, month) = value
+        .trim()
+        .split_once('-')
+        .ok_or_else(|| format!("月は 年目-月 の形式で指定してください: {}", value))?;
+    let year: usize = parse_integer("年目", year)?;
+    let month: usize = parse_integer("月", month)?;
+    if year == 0 || !(1..=12).contains(&month) {
+        return Err(format!("月は1年目以降の1〜12月で指定してください: {}", value));
+    }
+    Ok((year - 1) * 12 + month)
+}
+
+// ボーナス月の解析: 月,...:金額
+fn parse_bonus(name: &str, value: &str) -> Result<Adjustment, String> {
+    let (months, amount) = value
+        .split_once(':')
+        .ok_or_else(|| format!("{} は 月,...:金額 の形式で指定してください: {}", name, value))?;
+    let months = months
+        .split(',')
+        .map(|month| parse_integer(name, month))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Adjustment::Bonus {
+        months,
+        amount: parse_flag_amount(name, amount)?,
+    })
+}
+
+// 臨時の積立の解析: 年目-月:金額
+fn parse_deposit(name: &str, value: &str) -> Result<Adjustment, String> {
+    let (month, amount) = value
+        .split_once(':')
+        .ok_or_else(|| format!("{} は 年目-月:金額 の形式で指定してください: {}", name, value))?;
+    Ok(Adjustment::Deposit {
+        month: parse_plan_month(month).map_err(|e| format!("{}: {}", name, e))?,
+        amount: parse_flag_amount(name, amount)?,
+    })
+}
+
+// 休止期間の解析: 年目-月..年目-月
+fn parse_pause(name: &str, value: &str) -> Result<Adjustment, String> {
+    let (from, to) = value
+        .split_once("..")
+        .ok_or_else(|| format!("{} は 年目-月..年目-月 の形式で指定してください: {}", name, value))?;
+    Ok(Adjustment::Pause {
+        from: parse_plan_month(from).map_err(|e| format!("{}: {}", name, e))?,
+        to: parse_plan_month(to).map_err(|e| format!("{}: {}", name, e))?,
+    })
+}
+
+// ファンドの解析: 名前:信託報酬[:購入時手数料[:信託財産留保額]] (いずれも %)
+fn parse_fund(name: &str, value: &str) -> Result<Fund, String> {
+    let mut parts = value.split(':').map(str::trim);
+    let fund_name = parts.next().unwrap_or_default().to_string();
+    let rates = parts.map(|part| parse_rate(name, part)).collect::<Result<Vec<_>, _>>()?;
+    if fund_name.is_empty() || rates.is_empty() || rates.len() > 3 {
+        return Err(format!(
+            "{} は 名前:信託報酬[:購入時手数料[:信託財産留保額]] の形式で指定してください: {}",
+            name, value
+        ));
+    }
+
+    Ok(Fund {
+        name: fund_name,
+        fees: FundFees {
+            expense_ratio: rates[0],
+            front_load: rates.get(1).copied().unwrap_or(0.0),
+            retention_fee: rates.get(2).copied().unwrap_or(0.0),
+        },
+    })
+}
+
+// 整数の解析（試行回数やシード）
+pub fn parse_integer<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .replace([',', '_'], "")
+        .parse()
+        .map_err(|_| format!("{} の値を解釈できません: {}", name, value))
+}
+
+// 期間の解析: カンマ区切りの年数
+pub fn parse_years(name: &str, value: &str) -> Result<Vec<usize>, String> {
+    let mut periods = Vec::new();
+    for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let years: usize = part
+            .trim_end_matches('年')
+            .parse()
+            .map_err(|_| format!("{} の年数を解釈できません: {}", name, part))?;
+        if years == 0 || years > 100 {
+            return Err(format!("{} には1〜100年の範囲で指定してください: {}", name, part));
+        }
+        periods.push(years);
+    }
+
+    if periods.is_empty() {
+        return Err(format!("{} には少なくとも1つの期間を指定してください", name));
+    }
+    periods.sort_unstable();
+    periods.dedup();
+    Ok(periods)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, Error> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    // 一時ディレクトリにシナリオファイルを書き出す（テストごとに別の名前を使う）
+    fn scenario_file(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    // 年ごとの月額と --monthly は、コマンドラインでもシナリオファイルとの組み合わせでも拒否すること
+    #[test]
+    fn rejects_monthly_with_yearly_schedule() {
+        assert!(matches!(parse(&["--monthly", "3万", "--yearly-monthly", "5万,6万"]), Err(Error::Argument(_))));
+
+        let path = scenario_file("yearly-schedule", "[contribution]\nyearly = [\"5万\", \"6万\"]\n");
+        let with_monthly = parse(&["--scenario", path.to_str().unwrap(), "--monthly", "3万"]);
+        let without_monthly = parse(&["--scenario", path.to_str().unwrap()]);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(with_monthly, Err(Error::Argument(_))));
+        let Ok(Command::Run(plan)) = without_monthly else {
+            panic!("年ごとの月額だけのシナリオファイルは受け付けること");
+        };
+        assert_eq!(plan.current_monthly, 50_000.0);
+    }
+
+    // 実効年利と複利の頻度の組み合わせは、どちらをシナリオファイルで指定しても拒否すること
+    #[test]
+    fn rejects_compounding_with_effective_rate() {
+        let is_compounding_error = |result: Result<Command, Error>| {
+            matches!(result, Err(Error::InvalidPlan { error, .. }) if error.field == "compounding")
+        };
+        assert!(is_compounding_error(parse(&["--rate-basis", "effective", "--compounding", "daily"])));
+
+        let daily = scenario_file("daily-compounding", "compounding = \"daily\"\n");
+        let effective = scenario_file("effective-rate", "rate_basis = \"effective\"\n");
+        let results = [
+            parse(&["--scenario", daily.to_str().unwrap(), "--rate-basis", "effective"]),
+            parse(&["--scenario", effective.to_str().unwrap(), "--compounding", "daily"]),
+        ];
+        // コマンドラインで名目年利に戻せば、シナリオファイルの複利の頻度を使える
+        let nominal = parse(&["--scenario", effective.to_str().unwrap(), "--rate-basis", "nominal", "--compounding", "daily"]);
+        std::fs::remove_file(&daily).unwrap();
+        std::fs::remove_file(&effective).unwrap();
+        assert!(results.into_iter().all(is_compounding_error));
+        assert!(matches!(nominal, Ok(Command::Run(_))));
+    }
+}
@@ -0,0 +1,167 @@
+// 年利の扱い: 名目年利か実効年利か、名目年利の場合は複利の頻度
+//
+// シミュレーションは月ごとに運用するので、入力された年利を月次の利率に換算する。
+// 各シミュレーション・逆算の関数は「年利 / 12 = 月次の利率」とするので、
+// それらには月次の利率を12倍した年利（月複利の名目年利）を渡す。
+//   名目年利 r、年 m 回の複利: 月次の利率 = (1 + r / m)^(m / 12) - 1
+//   連続複利:                  月次の利率 = e^(r / 12) - 1
+//   実効年利 e:                月次の利率 = (1 + e)^(1 / 12) - 1
+
+use serde::Deserialize;
+
+use crate::error::PlanError;
+
+// 名目年利の複利の頻度
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compounding {
+    #[default]
+    Monthly,
+    Daily,
+    Annual,
+    Continuous,
+}
+
+impl Compounding {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "monthly" => Ok(Compounding::Monthly),
+            "daily" => Ok(Compounding::Daily),
+            "annual" | "yearly" => Ok(Compounding::Annual),
+            "continuous" => Ok(Compounding::Continuous),
+            _ => Err(format!(
+                "複利の頻度は monthly / daily / annual / continuous のいずれかを指定してください: {}",
+                value
+            )),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Compounding::Monthly => "月複利",
+            Compounding::Daily => "日複利",
+            Compounding::Annual => "年複利",
+            Compounding::Continuous => "連続複利",
+        }
+    }
+}
+
+// 入力された年利の種類
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateBasis {
+    // 名目年利（複利の頻度で月次の利率に換算する）
+    #[default]
+    Nominal,
+    // 実効年利（1年間運用したときの実際の増加率。複利の頻度は使わない）
+    Effective,
+}
+
+impl RateBasis {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "nominal" => Ok(RateBasis::Nominal),
+            "effective" => Ok(RateBasis::Effective),
+            _ => Err(format!("年利の種類は nominal または effective を指定してください: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RateConvention {
+    pub basis: RateBasis,
+    pub compounding: Compounding,
+}
+
+impl RateConvention {
+    // 実効年利には複利の頻度がないので、既定（月複利）以外の頻度との組み合わせは拒否する
+    // （シナリオファイルとコマンドラインのどちらで指定したかによらず、合わせた結果で確認する）
+    pub fn validate(&self) -> Result<(), PlanError> {
+        if self.basis == RateBasis::Effective && self.compounding != Compounding::default() {
+            return Err(PlanError::new(
+                "compounding",
+                "複利の頻度は名目年利 (rate_basis = \"nominal\") の場合に指定してください",
+            ));
+        }
+        Ok(())
+    }
+
+    // 年利から月次の利率
+    pub fn monthly_rate(&self, annual_rate: f64) -> f64 {
+        match (self.basis, self.compounding) {
+            (RateBasis::Effective, _) | (RateBasis::Nominal, Compounding::Annual) => {
+                (1.0 + annual_rate).powf(1.0 / 12.0) - 1.0
+            }
+            (RateBasis::Nominal, Compounding::Monthly) => annual_rate / 12.0,
+            (RateBasis::Nominal, Compounding::Daily) => (1.0 + annual_rate / 365.0).powf(365.0 / 12.0) - 1.0,
+            (RateBasis::Nominal, Compounding::Continuous) => (annual_rate / 12.0).exp() - 1.0,
+        }
+    }
+
+    // 月次の利率から、この年利の種類での年利に戻す（monthly_rate の逆）
+    pub fn annual_rate(&self, monthly_rate: f64) -> f64 {
+        match (self.basis, self.compounding) {
+            (RateBasis::Effective, _) | (RateBasis::Nominal, Compounding::Annual) => {
+                (1.0 + monthly_rate).powi(12) - 1.0
+            }
+            (RateBasis::Nominal, Compounding::Monthly) => monthly_rate * 12.0,
+            (RateBasis::Nominal, Compounding::Daily) => ((1.0 + monthly_rate).powf(12.0 / 365.0) - 1.0) * 365.0,
+            (RateBasis::Nominal, Compounding::Continuous) => (1.0 + monthly_rate).ln() * 12.0,
+        }
+    }
+
+    // 各シミュレーションに渡す年利（月複利の名目年利）
+    pub fn monthly_compounded(&self, annual_rate: f64) -> f64 {
+        self.monthly_rate(annual_rate) * 12.0
+    }
+
+    // 実効年利
+    pub fn effective_rate(&self, annual_rate: f64) -> f64 {
+        (1.0 + self.monthly_rate(annual_rate)).powi(12) - 1.0
+    }
+
+    pub fn describe(&self) -> String {
+        match self.basis {
+            RateBasis::Effective => "実効年利".to_string(),
+            RateBasis::Nominal => format!("{}の名目年利", self.compounding.label()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convention(basis: RateBasis, compounding: Compounding) -> RateConvention {
+        RateConvention { basis, compounding }
+    }
+
+    #[test]
+    fn effective_rate_of_nominal_rates() {
+        let cases = [
+            (Compounding::Monthly, 0.051161897881733),
+            (Compounding::Daily, 0.051267496467462),
+            (Compounding::Annual, 0.05),
+            (Compounding::Continuous, 0.051271096376024),
+        ];
+        for (compounding, expected) in cases {
+            let effective = convention(RateBasis::Nominal, compounding).effective_rate(0.05);
+            assert!((effective - expected).abs() < 1e-12, "{:?}: {}", compounding, effective);
+        }
+        let effective = convention(RateBasis::Effective, Compounding::Daily).effective_rate(0.05);
+        assert!((effective - 0.05).abs() < 1e-12);
+    }
+
+    #[test]
+    fn annual_rate_inverts_monthly_rate() {
+        for basis in [RateBasis::Nominal, RateBasis::Effective] {
+            for compounding in [Compounding::Monthly, Compounding::Daily, Compounding::Annual, Compounding::Continuous] {
+                let convention = convention(basis, compounding);
+                for rate in [-0.03, 0.0, 0.05, 0.12] {
+                    let back = convention.annual_rate(convention.monthly_rate(rate));
+                    assert!((back - rate).abs() < 1e-12, "{:?}: {} != {}", convention, back, rate);
+                }
+            }
+        }
+    }
+}
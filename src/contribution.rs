@@ -0,0 +1,251 @@
+// 毎月の積立額のスケジュール: 昇給に合わせた増額など、年ごとに積立額を変える
+//
+// いずれのスケジュールも1年目の月額（現在の月額投資）を起点とする。
+// 年齢別・年ごとの金額は指定した金額をそのまま使うが、必要月額を逆算するときは
+// 1年目の月額に比例して全体を拡大・縮小する（スケジュールの形は保つ）。
+// 年齢別で現在の月額が0の場合は、1年目の月額の代わりに最初の0でない段階の月額を基準にする。
+
+use crate::error::PlanError;
+
+// ある年齢からの毎月の積立額
+#[derive(Debug, Clone)]
+pub struct AgeTier {
+    pub age: u32,
+    pub monthly: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum ContributionSchedule {
+    // 一定額
+    #[default]
+    Flat,
+    // 毎年、前年の月額の一定割合ずつ増額する
+    PercentStep(f64),
+    // 毎年、一定額ずつ増額する
+    AmountStep(f64),
+    // 指定した年齢になったらその月額に変える（最初の段階より前は現在の月額）
+    AgeTiers(Vec<AgeTier>),
+    // 年ごとの月額（1年目から。最後の月額を以降の年にも使う）
+    Yearly(Vec<f64>),
+}
+
+impl ContributionSchedule {
+    pub fn validate(&self, current_age: Option<u32>) -> Result<(), PlanError> {
+        let check_amount = |amount: f64| {
+            if amount.is_finite() && amount >= 0.0 {
+                Ok(())
+            } else {
+                Err(PlanError::new("contribution", format!("積立額には0以上の金額を指定してください: {}", amount)))
+            }
+        };
+
+        match self {
+            ContributionSchedule::Flat => Ok(()),
+            ContributionSchedule::PercentStep(rate) => {
+                if rate.is_finite() && *rate > -1.0 {
+                    Ok(())
+                } else {
+                    Err(PlanError::new("contribution.step_rate", "増額率には-100%より大きい値を指定してください"))
+                }
+            }
+            ContributionSchedule::AmountStep(amount) => {
+                if amount.is_finite() && *amount >= 0.0 {
+                    Ok(())
+                } else {
+                    Err(PlanError::new("contribution.step_amount", "増額する金額には0以上の金額を指定してください"))
+                }
+            }
+            ContributionSchedule::AgeTiers(tiers) => {
+                if current_age.is_none() {
+                    return Err(PlanError::new("age", "年齢別の積立額には現在の年齢が必要です"));
+                }
+                if tiers.is_empty() {
+                    return Err(PlanError::new("contribution.tiers", "年齢別の積立額を1つ以上指定してください"));
+                }
+                if tiers.windows(2).any(|w| w[0].age >= w[1].age) {
+                    return Err(PlanError::new("contribution.tiers", "年齢は昇順に重複なく指定してください"));
+                }
+                tiers.iter().try_for_each(|tier| check_amount(tier.monthly))
+            }
+            ContributionSchedule::Yearly(amounts) => {
+                match amounts.first() {
+                    Some(first) if *first > 0.0 => {}
+                    _ => return Err(PlanError::new("contribution.yearly", "1年目の月額には正の金額を指定してください")),
+                }
+                amounts.iter().try_for_each(|&amount| check_amount(amount))
+            }
+        }
+    }
+
+    pub fn is_flat(&self) -> bool {
+        matches!(self, ContributionSchedule::Flat)
+    }
+
+    // スケジュールを拡大・縮小するときの基準の月額（通常は現在の月額。
+    // 年齢別で現在の月額が0なら、最初の0でない段階の月額）
+    pub fn base_monthly(&self, current_monthly: f64) -> f64 {
+        match self {
+            ContributionSchedule::AgeTiers(tiers) if current_monthly <= 0.0 => tiers
+                .iter()
+                .map(|tier| tier.monthly)
+                .find(|&monthly| monthly > 0.0)
+                .unwrap_or(0.0),
+            _ => current_monthly,
+        }
+    }
+
+    // year 年目（0始まり）の月額
+    //   start: 基準の月額（base_monthly）をこの額にしたときの月額を求める
+    //   current_monthly: スケジュールを指定したときの1年目の月額
+    //   age: その年の年齢
+    pub fn monthly_for_year(&self, start: f64, current_monthly: f64, year: usize, age: Option<u32>) -> f64 {
+        let base = self.base_monthly(current_monthly);
+        let scale = if base > 0.0 { start / base } else { 1.0 };
+        match self {
+            ContributionSchedule::Flat => start,
+            ContributionSchedule::PercentStep(rate) => start * (1.0 + rate).powi(year as i32),
+            ContributionSchedule::AmountStep(amount) => start + amount * year as f64,
+            ContributionSchedule::AgeTiers(tiers) => {
+                let tier = age.and_then(|age| tiers.iter().rev().find(|tier| tier.age <= age));
+                match tier {
+                    Some(tier) => tier.monthly * scale,
+                    None => current_monthly * scale,
+                }
+            }
+            ContributionSchedule::Yearly(amounts) => {
+                let last = amounts.len().saturating_sub(1);
+                amounts.get(year.min(last)).copied().unwrap_or(0.0) * scale
+            }
+        }
+    }
+}
+
+// 月ごとの積立額の調整（シミュレーションは1月開始とし、経過月は1始まり）
+#[derive(Debug, Clone)]
+pub enum Adjustment {
+    // 毎年、指定した月（1〜12月）にボーナスから上乗せする
+    Bonus { months: Vec<u32>, amount: f64 },
+    // 指定した経過月に一度だけ上乗せする
+    Deposit { month: usize, amount: f64 },
+    // 指定した経過月の範囲（両端を含む）は積立を止める（育休や転職など）
+    Pause { from: usize, to: usize },
+}
+
+impl Adjustment {
+    pub fn validate(&self) -> Result<(), PlanError> {
+        match self {
+            Adjustment::Bonus { months, amount } => {
+                if months.is_empty() || months.iter().any(|month| !(1..=12).contains(month)) {
+                    return Err(PlanError::new("contribution.bonus_months", "ボーナス月は1〜12月で指定してください"));
+                }
+                if !amount.is_finite() || *amount < 0.0 {
+                    return Err(PlanError::new("contribution.bonus", "ボーナスからの積立額には0以上の金額を指定してください"));
+                }
+            }
+            Adjustment::Deposit { month, amount } => {
+                if *month == 0 {
+                    return Err(PlanError::new("contribution.deposits", "月は1年目の1月以降を指定してください"));
+                }
+                if !amount.is_finite() || *amount < 0.0 {
+                    return Err(PlanError::new("contribution.deposits", "臨時の積立額には0以上の金額を指定してください"));
+                }
+            }
+            Adjustment::Pause { from, to } => {
+                if *from == 0 || from > to {
+                    return Err(PlanError::new("contribution.pauses", "休止期間は開始月 ≦ 終了月 で指定してください"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // 経過月 month（1始まり）に適用されるか
+    pub fn applies_to(&self, month: usize) -> bool {
+        match self {
+            Adjustment::Bonus { months, .. } => months.contains(&(((month - 1) % 12) as u32 + 1)),
+            Adjustment::Deposit { month: at, .. } => *at == month,
+            Adjustment::Pause { from, to } => (*from..=*to).contains(&month),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Adjustment::Bonus { .. } => "ボーナス",
+            Adjustment::Deposit { .. } => "臨時の積立",
+            Adjustment::Pause { .. } => "休止",
+        }
+    }
+}
+
+// 各月の積立額に調整を反映する。休止中は通常の積立とボーナスを止め、臨時の積立だけを行う
+pub fn apply_adjustments(contributions: &mut [f64], adjustments: &[Adjustment]) {
+    for (index, contribution) in contributions.iter_mut().enumerate() {
+        let month = index + 1;
+        let paused = adjustments
+            .iter()
+            .any(|adjustment| matches!(adjustment, Adjustment::Pause { .. }) && adjustment.applies_to(month));
+        if paused {
+            *contribution = 0.0;
+        }
+
+        for adjustment in adjustments.iter().filter(|adjustment| adjustment.applies_to(month)) {
+            match adjustment {
+                Adjustment::Bonus { amount, .. } if !paused => *contribution += amount,
+                Adjustment::Deposit { amount, .. } => *contribution += amount,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2年間、毎月1万円の積立に調整を反映した各月の積立額（index 0 が1ヶ月目）
+    fn adjusted(adjustments: &[Adjustment]) -> Vec<f64> {
+        let mut contributions = vec![10_000.0; 24];
+        apply_adjustments(&mut contributions, adjustments);
+        contributions
+    }
+
+    fn bonus() -> Adjustment {
+        Adjustment::Bonus { months: vec![6, 12], amount: 50_000.0 }
+    }
+
+    // 休止中のボーナス月は積み立てず、休止の前後のボーナスは上乗せすること
+    #[test]
+    fn pause_overlapping_a_bonus_month() {
+        let contributions = adjusted(&[bonus(), Adjustment::Pause { from: 5, to: 7 }]);
+        assert_eq!(contributions[3], 10_000.0);
+        assert_eq!(&contributions[4..7], &[0.0; 3]);
+        assert_eq!(contributions[7], 10_000.0);
+        assert_eq!(contributions[11], 60_000.0);
+        assert_eq!(contributions[17], 60_000.0);
+    }
+
+    // 年をまたぐ休止は翌年の月まで止め、休止中でも臨時の積立は行うこと
+    #[test]
+    fn pause_across_a_year_boundary() {
+        let contributions = adjusted(&[
+            bonus(),
+            Adjustment::Pause { from: 11, to: 14 },
+            Adjustment::Deposit { month: 13, amount: 200_000.0 },
+        ]);
+        assert_eq!(contributions[9], 10_000.0);
+        assert_eq!(&contributions[10..14], &[0.0, 0.0, 200_000.0, 0.0]);
+        assert_eq!(contributions[14], 10_000.0);
+        assert_eq!(contributions[5], 60_000.0);
+        assert_eq!(contributions[17], 60_000.0);
+    }
+
+    // 期間の終わりを超える休止は、期間内の月だけを止めること
+    #[test]
+    fn pause_past_the_horizon() {
+        let contributions = adjusted(&[bonus(), Adjustment::Pause { from: 20, to: 600 }]);
+        assert_eq!(contributions.len(), 24);
+        assert_eq!(contributions[18], 10_000.0);
+        assert_eq!(&contributions[19..], &[0.0; 5]);
+        assert_eq!(contributions.iter().sum::<f64>(), 10_000.0 * 19.0 + 50_000.0 * 3.0);
+    }
+}
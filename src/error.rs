@@ -0,0 +1,58 @@
+// 入力の誤りの種類
+//
+// どの段階で失敗したかによって表示を変えられるように、文字列ではなく種類ごとに分ける。
+
+use std::fmt;
+use std::path::PathBuf;
+
+// 計画の値の誤り（問題のある項目名とその理由）
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanError {
+    pub field: String,
+    pub message: String,
+}
+
+impl PlanError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        PlanError {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    // コマンドライン引数の誤り（不明なオプション・値の形式など）
+    Argument(String),
+    // シナリオファイルを読み込めない、または解析できない
+    Scenario { path: PathBuf, message: String },
+    // 計画の値の誤り（シナリオファイルを使った場合はそのパス）
+    InvalidPlan { error: PlanError, scenario: Option<PathBuf> },
+    // 過去データを読み込めない
+    History(String),
+}
+
+impl Error {
+    // 使い方の案内を添えるべき誤りか（過去データの読み込みは引数の誤りではない）
+    pub fn is_usage(&self) -> bool {
+        !matches!(self, Error::History(_))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Argument(message) | Error::History(message) => write!(f, "{}", message),
+            Error::Scenario { path, message } => write!(f, "{}: {}", path.display(), message),
+            Error::InvalidPlan { error, scenario: Some(path) } => write!(f, "{}: {}", path.display(), error),
+            Error::InvalidPlan { error, scenario: None } => write!(f, "{}", error),
+        }
+    }
+}
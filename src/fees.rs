@@ -0,0 +1,161 @@
+// ファンドの手数料: 信託報酬（経費率）、購入時手数料、信託財産留保額
+//
+// 信託報酬は日々差し引かれるが、ここでは毎月の運用後に 年率 / 12 を差し引く。
+// 購入時手数料は買付金額に対する率で、毎月の積立額（手数料込み）から差し引かれる。
+// 信託財産留保額は売却時に売却額に対してかかる。
+
+use crate::error::PlanError;
+use crate::simulation::grow_with_contribution;
+use crate::tvm::PaymentTiming;
+
+#[derive(Debug, Clone, Default)]
+pub struct FundFees {
+    // 信託報酬（年率）
+    pub expense_ratio: f64,
+    // 購入時手数料（買付金額に対する率）
+    pub front_load: f64,
+    // 信託財産留保額（売却額に対する率）
+    pub retention_fee: f64,
+}
+
+impl FundFees {
+    pub fn validate(&self, name: &str) -> Result<(), PlanError> {
+        let check = |value: f64, field: &str| {
+            if (0.0..0.2).contains(&value) {
+                Ok(())
+            } else {
+                Err(PlanError::new(format!("{}.{}", name, field), "手数料は0%以上20%未満で指定してください"))
+            }
+        };
+        check(self.expense_ratio, "expense_ratio")?;
+        check(self.front_load, "front_load")?;
+        check(self.retention_fee, "retention_fee")?;
+        Ok(())
+    }
+}
+
+// 比較するファンド
+#[derive(Debug, Clone)]
+pub struct Fund {
+    pub name: String,
+    pub fees: FundFees,
+}
+
+impl Fund {
+    pub fn validate(&self, name: &str) -> Result<(), PlanError> {
+        if self.name.trim().is_empty() {
+            return Err(PlanError::new(format!("{}.name", name), "ファンド名を指定してください"));
+        }
+        self.fees.validate(name)
+    }
+}
+
+// 年末時点の資産と、それまでに支払った手数料の累計
+#[derive(Debug, Clone, Copy)]
+pub struct FeeYear {
+    pub wealth: f64,
+    pub front_load_paid: f64,
+    pub expense_paid: f64,
+}
+
+impl FeeYear {
+    // この時点ですべて売却した場合の信託財産留保額
+    pub fn retention_fee(&self, fees: &FundFees) -> f64 {
+        self.wealth * fees.retention_fee
+    }
+
+    // 売却時の信託財産留保額を差し引いた受取額
+    pub fn proceeds(&self, fees: &FundFees) -> f64 {
+        self.wealth - self.retention_fee(fees)
+    }
+
+    // 支払った手数料の合計（売却時の信託財産留保額を含む）
+    pub fn total_fees(&self, fees: &FundFees) -> f64 {
+        self.front_load_paid + self.expense_paid + self.retention_fee(fees)
+    }
+}
+
+// 手数料を差し引きながらシミュレーション
+pub fn simulate_with_fees(
+    initial_balance: f64,
+    // 各月の積立額（contributions[0] が1ヶ月目）
+    contributions: &[f64],
+    annual_rate: f64,
+    timing: PaymentTiming,
+    fees: &FundFees,
+) -> Vec<FeeYear> {
+    let monthly_rate = annual_rate / 12.0;
+    let monthly_expense = fees.expense_ratio / 12.0;
+    // 初期資産はすでにファンドで保有しているものとみなし、購入時手数料はかからない
+    let mut wealth = initial_balance;
+    let mut front_load_paid = 0.0;
+    let mut expense_paid = 0.0;
+    let mut yearly = Vec::with_capacity(contributions.len() / 12);
+
+    for (index, &contribution) in contributions.iter().enumerate() {
+        let month = index + 1;
+
+        // 毎月の積立（購入時手数料を差し引いた分が買い付けられる）
+        let invested = contribution / (1.0 + fees.front_load);
+        front_load_paid += contribution - invested;
+
+        // 月次の利息
+        wealth = grow_with_contribution(wealth, invested, monthly_rate, timing);
+
+        // 信託報酬
+        let expense = wealth * monthly_expense;
+        expense_paid += expense;
+        wealth -= expense;
+
+        // 年末の資産を記録
+        if month % 12 == 0 {
+            yearly.push(FeeYear {
+                wealth,
+                front_load_paid,
+                expense_paid,
+            });
+        }
+    }
+
+    yearly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::simulate_index_investment;
+
+    // 手数料がすべて0%なら、手数料なしのシミュレーションと同じ結果になること
+    #[test]
+    fn zero_fees_match_the_plain_simulation() {
+        let contributions: Vec<f64> = (0..120).map(|month| 30_000.0 + 1_000.0 * (month / 12) as f64).collect();
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            let plain = simulate_index_investment(1_000_000.0, &contributions, 0.05, timing);
+            let with_fees = simulate_with_fees(1_000_000.0, &contributions, 0.05, timing, &FundFees::default());
+            assert_eq!(with_fees.len(), plain.len());
+            for (year, plain) in with_fees.iter().zip(&plain) {
+                assert!((year.wealth - plain).abs() < 1e-6, "{:?}: {} != {}", timing, year.wealth, plain);
+                assert_eq!(year.total_fees(&FundFees::default()), 0.0);
+            }
+        }
+    }
+
+    // 年利0%・信託報酬 年1.2%（月0.1%）・購入時手数料2%で、毎月10,200円（買付10,000円）を月初に積み立てると
+    //   資産 = 100万 × 0.999^12 + 1万 × (0.999 + 0.999^2 + … + 0.999^12) = 1,107,288.63円
+    //   信託報酬 = 初期資産と買付額の合計 - 資産 = 112万 - 1,107,288.63 = 12,711.37円、購入時手数料 = 200円 × 12
+    #[test]
+    fn monthly_fees_match_hand_computed_values() {
+        let fees = FundFees {
+            expense_ratio: 0.012,
+            front_load: 0.02,
+            retention_fee: 0.005,
+        };
+        let year = simulate_with_fees(1_000_000.0, &[10_200.0; 12], 0.0, PaymentTiming::Beginning, &fees)[0];
+        assert!((year.wealth - 1_107_288.633357).abs() < 1e-5, "{}", year.wealth);
+        assert!((year.expense_paid - 12_711.366643).abs() < 1e-5, "{}", year.expense_paid);
+        assert!((year.front_load_paid - 2_400.0).abs() < 1e-9);
+        // 売却時には資産の0.5%が差し引かれる
+        assert!((year.proceeds(&fees) - 1_107_288.633357 * 0.995).abs() < 1e-5);
+        assert!((year.total_fees(&fees) - (12_711.366643 + 2_400.0 + 1_107_288.633357 * 0.005)).abs() < 1e-5);
+    }
+}
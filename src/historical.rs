@@ -0,0 +1,326 @@
+// 過去の指数データ（CSV）による積立の再現
+//
+// CSV は1行目がヘッダーで、1列目が年月、2列目が月末の価格または月次リターン。
+//   date,price            date,return
+//   1990-01,329.08        1990-01,-0.0688
+//   1990-02,331.89        1990-02,0.85%
+// 年月は YYYY-MM / YYYY-MM-DD / YYYY/MM/DD のいずれか。月は連続している必要がある。
+// リターンは小数 (0.0085) または % 付き (0.85%) で指定する。# で始まる行は無視する。
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::monte_carlo::PERCENTILES;
+use crate::simulation::simulate_with_monthly_returns;
+use crate::tvm::PaymentTiming;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct YearMonth {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl YearMonth {
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split(['-', '/']);
+        let year = parts.next()?.trim().parse().ok()?;
+        let month = parts.next()?.trim().parse().ok()?;
+        if !(1..=12).contains(&month) {
+            return None;
+        }
+        Some(YearMonth { year, month })
+    }
+
+    // n ヶ月後の年月
+    pub fn add_months(self, months: usize) -> Self {
+        let index = self.year as i64 * 12 + (self.month as i64 - 1) + months as i64;
+        YearMonth {
+            year: index.div_euclid(12) as i32,
+            month: index.rem_euclid(12) as u32 + 1,
+        }
+    }
+}
+
+impl fmt::Display for YearMonth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+// 月次リターンの系列。returns[i] は first_month から i ヶ月後の月のリターン
+#[derive(Debug, Clone)]
+pub struct ReturnSeries {
+    pub first_month: YearMonth,
+    pub returns: Vec<f64>,
+}
+
+impl ReturnSeries {
+    pub fn last_month(&self) -> YearMonth {
+        self.first_month.add_months(self.returns.len().saturating_sub(1))
+    }
+}
+
+enum ValueKind {
+    Price,
+    Return,
+}
+
+pub fn load_csv(path: &Path) -> Result<ReturnSeries, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("{}: CSVファイルを読み込めません: {}", path.display(), e))?;
+    parse_csv(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_csv(text: &str) -> Result<ReturnSeries, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let (header_line, header) = lines.next().ok_or("データがありません")?;
+    let columns: Vec<&str> = split_row(header);
+    if columns.len() < 2 {
+        return Err(format!("{}行目: ヘッダーには年月と価格（またはリターン）の2列が必要です", header_line));
+    }
+    let kind = match columns[1].to_ascii_lowercase().as_str() {
+        "price" | "close" | "価格" | "終値" | "基準価額" => ValueKind::Price,
+        "return" | "returns" | "リターン" | "騰落率" => ValueKind::Return,
+        other => {
+            return Err(format!(
+                "{}行目: 2列目の列名 \"{}\" は price または return にしてください",
+                header_line, other
+            ));
+        }
+    };
+
+    let mut first_month: Option<YearMonth> = None;
+    let mut previous_month: Option<YearMonth> = None;
+    let mut values = Vec::new();
+
+    for (line_number, line) in lines {
+        let row = split_row(line);
+        if row.len() < 2 {
+            return Err(format!("{}行目: 列が足りません", line_number));
+        }
+
+        let month = YearMonth::parse(row[0])
+            .ok_or_else(|| format!("{}行目: 年月を解釈できません: {}", line_number, row[0]))?;
+        if let Some(previous) = previous_month
+            && month != previous.add_months(1)
+        {
+            return Err(format!(
+                "{}行目: 年月が連続していません ({} の次が {})",
+                line_number, previous, month
+            ));
+        }
+
+        let value = parse_value(row[1])
+            .ok_or_else(|| format!("{}行目: 値を解釈できません: {}", line_number, row[1]))?;
+        if matches!(kind, ValueKind::Price) && value <= 0.0 {
+            return Err(format!("{}行目: 価格は正の値である必要があります: {}", line_number, row[1]));
+        }
+        if matches!(kind, ValueKind::Return) && value <= -1.0 {
+            return Err(format!("{}行目: リターンは-100%より大きい値である必要があります: {}", line_number, row[1]));
+        }
+
+        first_month.get_or_insert(month);
+        previous_month = Some(month);
+        values.push(value);
+    }
+
+    let first_month = first_month.ok_or("データ行がありません")?;
+    match kind {
+        // 価格の場合は前月比をその月のリターンとする（最初の月はリターンなし）
+        ValueKind::Price => {
+            if values.len() < 2 {
+                return Err("価格データは2ヶ月分以上必要です".to_string());
+            }
+            let returns = values.windows(2).map(|w| w[1] / w[0] - 1.0).collect();
+            Ok(ReturnSeries {
+                first_month: first_month.add_months(1),
+                returns,
+            })
+        }
+        ValueKind::Return => Ok(ReturnSeries {
+            first_month,
+            returns: values,
+        }),
+    }
+}
+
+fn split_row(line: &str) -> Vec<&str> {
+    line.split(',').map(|cell| cell.trim().trim_matches('"')).collect()
+}
+
+fn parse_value(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let number = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().ok()? / 100.0,
+        None => value.parse().ok()?,
+    };
+    number.is_finite().then_some(number)
+}
+
+// 開始月ごとの積立結果
+#[derive(Debug, Clone)]
+pub struct WindowOutcome {
+    pub start: YearMonth,
+    pub end: YearMonth,
+    pub final_wealth: f64,
+}
+
+// 取り得るすべての開始月について、指定年数の積立を過去のリターンで再現する
+pub fn rolling_windows(
+    series: &ReturnSeries,
+    initial_balance: f64,
+    // 各月の積立額（contributions[0] が開始月）
+    contributions: &[f64],
+    timing: PaymentTiming,
+) -> Vec<WindowOutcome> {
+    let months = contributions.len();
+    if months == 0 || series.returns.len() < months {
+        return Vec::new();
+    }
+
+    series
+        .returns
+        .windows(months)
+        .enumerate()
+        .map(|(offset, window)| {
+            let yearly_wealth = simulate_with_monthly_returns(initial_balance, contributions, window.iter().copied(), timing);
+            WindowOutcome {
+                start: series.first_month.add_months(offset),
+                end: series.first_month.add_months(offset + months - 1),
+                final_wealth: *yearly_wealth.last().unwrap_or(&0.0),
+            }
+        })
+        .collect()
+}
+
+// ある期間のすべての開始月を通した結果の要約
+#[derive(Debug, Clone)]
+pub struct WindowSummary {
+    pub years: usize,
+    pub count: usize,
+    // PERCENTILES と同じ順で、その順位に最も近い実際の開始月の結果
+    pub percentiles: Vec<WindowOutcome>,
+    pub worst: WindowOutcome,
+    pub best: WindowOutcome,
+}
+
+// 開始月ごとの結果を最終資産の順に並べ、最低・最高とパーセンタイル（P50 が中央値）を求める
+pub fn summarize_windows(outcomes: &[WindowOutcome], years: usize) -> Option<WindowSummary> {
+    if outcomes.is_empty() {
+        return None;
+    }
+
+    let mut sorted = outcomes.to_vec();
+    sorted.sort_by(|a, b| a.final_wealth.total_cmp(&b.final_wealth));
+
+    // 開始月を示せるよう、補間ではなく最も近い順位の結果を使う
+    let nearest = |p: f64| {
+        let index = ((p / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+        sorted[index].clone()
+    };
+
+    Some(WindowSummary {
+        years,
+        count: sorted.len(),
+        percentiles: PERCENTILES.iter().map(|&p| nearest(p)).collect(),
+        worst: sorted[0].clone(),
+        best: sorted[sorted.len() - 1].clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn month(year: i32, month: u32) -> YearMonth {
+        YearMonth { year, month }
+    }
+
+    // 価格は前月比のリターンに変換し、系列は2ヶ月目から始まること
+    #[test]
+    fn prices_become_monthly_returns() {
+        let series = parse_csv("date,price\n2000-01,100\n2000-02,110\n# コメント\n\n2000-03,99\n").unwrap();
+        assert_eq!(series.first_month, month(2000, 2));
+        assert_eq!(series.last_month(), month(2000, 3));
+        assert!((series.returns[0] - 0.1).abs() < 1e-12);
+        assert!((series.returns[1] + 0.1).abs() < 1e-12);
+    }
+
+    // リターンは小数でも % 付きでも読み込め、年月の形式と列名の別名も受け付けること
+    #[test]
+    fn returns_in_decimal_and_percent() {
+        let series = parse_csv("年月,騰落率\n1999/12/31,0.0085\n2000-01,-1.5%\n\"2000-02-29\",\" 2 % \"\n").unwrap();
+        assert_eq!(series.first_month, month(1999, 12));
+        let expected = [0.0085, -0.015, 0.02];
+        for (actual, expected) in series.returns.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-12, "{} != {}", actual, expected);
+        }
+    }
+
+    // 年月の抜け・重複・逆順と、意味のない値を行番号とともに拒否すること
+    #[test]
+    fn rejects_gaps_unsorted_dates_and_bad_values() {
+        let cases = [
+            ("date,price\n2000-01,100\n2000-03,110\n", "3行目: 年月が連続していません"),
+            ("date,price\n2000-02,100\n2000-01,110\n", "3行目: 年月が連続していません"),
+            ("date,return\n2000-01,0.01\n2000-01,0.02\n", "3行目: 年月が連続していません"),
+            ("date,return\n2000-13,0.01\n", "2行目: 年月を解釈できません"),
+            ("date,return\n2000-01,-100%\n", "2行目: リターンは-100%より大きい"),
+            ("date,price\n2000-01,0\n2000-02,1\n", "2行目: 価格は正の値"),
+            ("date,price\n2000-01,100\n", "価格データは2ヶ月分以上必要です"),
+            ("date,value\n2000-01,100\n", "1行目: 2列目の列名"),
+        ];
+        for (text, message) in cases {
+            let error = parse_csv(text).unwrap_err();
+            assert!(error.starts_with(message), "{:?}: {}", text, error);
+        }
+    }
+
+    // 開始月は系列の長さ - 期間 + 1 通りで、最初と最後の開始月・終了月が系列の端と一致すること
+    #[test]
+    fn rolling_windows_cover_every_start_month() {
+        let series = ReturnSeries {
+            first_month: month(2000, 11),
+            returns: (0..40).map(|index| index as f64 * 0.001).collect(),
+        };
+        let outcomes = rolling_windows(&series, 0.0, &[1.0; 24], PaymentTiming::Beginning);
+        assert_eq!(outcomes.len(), 40 - 24 + 1);
+        assert_eq!((outcomes[0].start, outcomes[0].end), (month(2000, 11), month(2002, 10)));
+        let last = outcomes.last().unwrap();
+        assert_eq!((last.start, last.end), (month(2002, 3), series.last_month()));
+
+        // 最初の開始月の最終資産は、その24ヶ月のリターンで積み立てた額
+        let expected = series.returns[..24].iter().fold(0.0, |wealth, r| (wealth + 1.0) * (1.0 + r));
+        assert!((outcomes[0].final_wealth - expected).abs() < 1e-12);
+
+        // 期間がデータより長い場合は再現できない
+        assert!(rolling_windows(&series, 0.0, &[1.0; 41], PaymentTiming::Beginning).is_empty());
+    }
+
+    #[test]
+    fn summary_picks_actual_start_months() {
+        let outcomes: Vec<WindowOutcome> = [5.0, 1.0, 4.0, 2.0, 3.0]
+            .iter()
+            .enumerate()
+            .map(|(offset, &final_wealth)| WindowOutcome {
+                start: month(2000, 1).add_months(offset),
+                end: month(2010, 12).add_months(offset),
+                final_wealth,
+            })
+            .collect();
+        let summary = summarize_windows(&outcomes, 11).unwrap();
+        assert_eq!(summary.count, 5);
+        assert_eq!((summary.worst.final_wealth, summary.worst.start), (1.0, month(2000, 2)));
+        assert_eq!((summary.best.final_wealth, summary.best.start), (5.0, month(2000, 1)));
+        // P5 / P25 / P50 / P75 / P95 は最も近い順位の開始月（補間しない）
+        let finals: Vec<f64> = summary.percentiles.iter().map(|outcome| outcome.final_wealth).collect();
+        assert_eq!(finals, [1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert!(summarize_windows(&[], 11).is_none());
+    }
+}
@@ -0,0 +1,324 @@
+// iDeCo（個人型確定拠出年金）: 掛金の上限、所得控除による節税額、一時金受取時の退職所得課税
+//
+// 掛金は全額が小規模企業共済等掛金控除の対象となり、所得税（復興特別所得税を含む）と
+// 住民税（10%）が軽減される。60歳以降に一時金で受け取る場合は、加入年数に応じた
+// 退職所得控除を差し引いた残りの1/2が退職所得として課税される。
+
+use serde::Deserialize;
+
+use crate::error::PlanError;
+use crate::simulation::grow_with_contribution;
+use crate::tvm::PaymentTiming;
+
+// 復興特別所得税を含めるための係数
+const RECONSTRUCTION_TAX_FACTOR: f64 = 1.021;
+// 住民税（所得割）の税率
+const RESIDENT_TAX_RATE: f64 = 0.10;
+// 受け取りを開始できる最も早い年齢
+pub const EARLIEST_PAYOUT_AGE: u32 = 60;
+// 掛金の月額の下限
+const MINIMUM_MONTHLY_CONTRIBUTION: f64 = 5_000.0;
+
+// 加入者の区分（掛金の上限が異なる）
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EmploymentCategory {
+    // 自営業者など（第1号被保険者）
+    SelfEmployed,
+    // 企業年金のない会社員
+    Employee,
+    // 企業型DC・DBに加入している会社員
+    EmployeeWithPension,
+    // 公務員
+    PublicServant,
+    // 専業主婦（夫）（第3号被保険者）
+    Dependent,
+}
+
+impl EmploymentCategory {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "self-employed" => Ok(EmploymentCategory::SelfEmployed),
+            "employee" => Ok(EmploymentCategory::Employee),
+            "employee-with-pension" => Ok(EmploymentCategory::EmployeeWithPension),
+            "public-servant" => Ok(EmploymentCategory::PublicServant),
+            "dependent" => Ok(EmploymentCategory::Dependent),
+            _ => Err(format!(
+                "加入者区分は self-employed / employee / employee-with-pension / public-servant / dependent のいずれかを指定してください: {}",
+                value
+            )),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EmploymentCategory::SelfEmployed => "自営業者",
+            EmploymentCategory::Employee => "会社員（企業年金なし）",
+            EmploymentCategory::EmployeeWithPension => "会社員（企業年金あり）",
+            EmploymentCategory::PublicServant => "公務員",
+            EmploymentCategory::Dependent => "専業主婦（夫）",
+        }
+    }
+
+    // 掛金の月額上限
+    pub fn monthly_cap(&self) -> f64 {
+        match self {
+            EmploymentCategory::SelfEmployed => 68_000.0,
+            EmploymentCategory::Employee => 23_000.0,
+            EmploymentCategory::EmployeeWithPension => 20_000.0,
+            EmploymentCategory::PublicServant => 20_000.0,
+            EmploymentCategory::Dependent => 23_000.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IdecoConfig {
+    pub category: EmploymentCategory,
+    // 毎月の掛金（None の場合は上限まで。現在の月額投資のうちiDeCoに回す分）
+    pub monthly_contribution: Option<f64>,
+    // 課税所得（節税額の計算に使用）
+    pub taxable_income: f64,
+    // 掛金を拠出する最後の年齢（この年齢になるまで拠出する）
+    pub contribution_end_age: u32,
+    // 一時金で受け取る年齢
+    pub payout_age: u32,
+}
+
+impl Default for IdecoConfig {
+    fn default() -> Self {
+        IdecoConfig {
+            category: EmploymentCategory::Employee,
+            monthly_contribution: None,
+            taxable_income: 4_000_000.0,
+            contribution_end_age: 60,
+            payout_age: 60,
+        }
+    }
+}
+
+impl IdecoConfig {
+    pub fn validate(&self, current_age: Option<u32>, current_monthly: f64) -> Result<(), PlanError> {
+        let Some(age) = current_age else {
+            return Err(PlanError::new("age", "iDeCoのシミュレーションには現在の年齢が必要です"));
+        };
+        // 掛金を指定しない場合は現在の月額投資から決まるので、その金額も同じ範囲で確認する
+        let contribution = self.contribution(current_monthly);
+        if !(contribution >= MINIMUM_MONTHLY_CONTRIBUTION && contribution <= self.category.monthly_cap()) {
+            let field = if self.monthly_contribution.is_some() { "ideco.monthly" } else { "monthly" };
+            return Err(PlanError::new(field, format!(
+                "{}の掛金は月5000円〜{}円の範囲で指定してください（掛金を指定しない場合は現在の月額投資を上限まで使います）: {}円",
+                self.category.label(),
+                self.category.monthly_cap(),
+                contribution
+            )));
+        }
+        if !self.taxable_income.is_finite() || self.taxable_income < 0.0 {
+            return Err(PlanError::new("ideco.taxable_income", "課税所得には0以上の金額を指定してください"));
+        }
+        if self.contribution_end_age > 65 {
+            return Err(PlanError::new("ideco.contribution_end_age", "掛金を拠出できるのは65歳までです"));
+        }
+        if age >= self.contribution_end_age {
+            return Err(PlanError::new("ideco.contribution_end_age", format!(
+                "現在の年齢 ({}歳) が拠出終了年齢 ({}歳) に達しています",
+                age, self.contribution_end_age
+            )));
+        }
+        if self.payout_age < EARLIEST_PAYOUT_AGE.max(self.contribution_end_age) || self.payout_age > 75 {
+            return Err(PlanError::new("ideco.payout_age", "受取年齢は60歳以上かつ拠出終了後、75歳以下で指定してください"));
+        }
+        Ok(())
+    }
+
+    // 実際の毎月の掛金
+    pub fn contribution(&self, current_monthly: f64) -> f64 {
+        self.monthly_contribution
+            .unwrap_or(current_monthly)
+            .min(self.category.monthly_cap())
+    }
+}
+
+// 所得税額（復興特別所得税を含まない。速算表による）
+pub fn income_tax(taxable_income: f64) -> f64 {
+    const BRACKETS: [(f64, f64, f64); 7] = [
+        (1_949_000.0, 0.05, 0.0),
+        (3_299_000.0, 0.10, 97_500.0),
+        (6_949_000.0, 0.20, 427_500.0),
+        (8_999_000.0, 0.23, 636_000.0),
+        (17_999_000.0, 0.33, 1_536_000.0),
+        (39_999_000.0, 0.40, 2_796_000.0),
+        (f64::INFINITY, 0.45, 4_796_000.0),
+    ];
+
+    if taxable_income <= 0.0 {
+        return 0.0;
+    }
+    let (_, rate, deduction) = BRACKETS
+        .iter()
+        .find(|(upper, _, _)| taxable_income <= *upper)
+        .copied()
+        .unwrap_or(BRACKETS[BRACKETS.len() - 1]);
+    taxable_income * rate - deduction
+}
+
+// 掛金の所得控除による1年間の節税額（所得税 + 復興特別所得税 + 住民税）
+pub fn annual_tax_saving(taxable_income: f64, annual_contribution: f64) -> f64 {
+    let deductible = annual_contribution.min(taxable_income).max(0.0);
+    let income_tax_saving = (income_tax(taxable_income) - income_tax(taxable_income - deductible))
+        * RECONSTRUCTION_TAX_FACTOR;
+    income_tax_saving + deductible * RESIDENT_TAX_RATE
+}
+
+// 退職所得控除額（加入年数は1年未満切り上げ）
+pub fn retirement_income_deduction(years: usize) -> f64 {
+    let years = years as f64;
+    if years <= 20.0 {
+        (400_000.0 * years).max(800_000.0)
+    } else {
+        8_000_000.0 + 700_000.0 * (years - 20.0)
+    }
+}
+
+// 一時金受取時の税額（所得税 + 復興特別所得税 + 住民税）
+pub fn lump_sum_tax(payout: f64, years: usize) -> f64 {
+    let retirement_income = ((payout - retirement_income_deduction(years)) / 2.0).max(0.0);
+    income_tax(retirement_income) * RECONSTRUCTION_TAX_FACTOR + retirement_income * RESIDENT_TAX_RATE
+}
+
+// 年末時点のiDeCoの状況
+#[derive(Debug, Clone)]
+pub struct IdecoYear {
+    pub age: u32,
+    pub balance: f64,
+    // その年の掛金による節税額
+    pub tax_saving: f64,
+}
+
+pub struct IdecoResult {
+    pub monthly_contribution: f64,
+    pub yearly: Vec<IdecoYear>,
+    pub contribution_years: usize,
+    pub payout: f64,
+    pub payout_tax: f64,
+}
+
+impl IdecoResult {
+    pub fn total_tax_saving(&self) -> f64 {
+        self.yearly.iter().map(|year| year.tax_saving).sum()
+    }
+}
+
+// 受取年齢までiDeCoを運用
+pub fn simulate_ideco(
+    current_monthly: f64,
+    annual_rate: f64,
+    timing: PaymentTiming,
+    current_age: u32,
+    config: &IdecoConfig,
+) -> IdecoResult {
+    let monthly_rate = annual_rate / 12.0;
+    let monthly_contribution = config.contribution(current_monthly);
+    let contribution_years = config.contribution_end_age.saturating_sub(current_age) as usize;
+    let total_years = config.payout_age.saturating_sub(current_age) as usize;
+
+    let mut balance = 0.0;
+    let mut yearly = Vec::with_capacity(total_years);
+
+    for year in 0..total_years {
+        let contributing = year < contribution_years;
+        let mut annual_contribution = 0.0;
+
+        for _ in 0..12 {
+            let contribution = if contributing { monthly_contribution } else { 0.0 };
+            annual_contribution += contribution;
+            balance = grow_with_contribution(balance, contribution, monthly_rate, timing);
+        }
+
+        yearly.push(IdecoYear {
+            age: current_age + year as u32 + 1,
+            balance,
+            tax_saving: annual_tax_saving(config.taxable_income, annual_contribution),
+        });
+    }
+
+    IdecoResult {
+        monthly_contribution,
+        yearly,
+        contribution_years,
+        payout: balance,
+        payout_tax: lump_sum_tax(balance, contribution_years),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "actual {} != expected {}", actual, expected);
+    }
+
+    #[test]
+    fn income_tax_at_bracket_boundaries() {
+        let cases = [
+            (-100_000.0, 0.0),
+            (1_949_000.0, 97_450.0),
+            (1_950_000.0, 97_500.0),
+            (3_299_000.0, 232_400.0),
+            (3_300_000.0, 232_500.0),
+            (6_949_000.0, 962_300.0),
+            (6_950_000.0, 962_500.0),
+            (9_000_000.0, 1_434_000.0),
+            (40_000_000.0, 13_204_000.0),
+        ];
+        for (taxable_income, expected) in cases {
+            assert_close(income_tax(taxable_income), expected);
+        }
+    }
+
+    #[test]
+    fn retirement_income_deduction_by_years() {
+        // 20年までは1年40万円（最低80万円）、20年を超える分は1年70万円
+        let cases = [(1, 800_000.0), (2, 800_000.0), (3, 1_200_000.0), (20, 8_000_000.0), (21, 8_700_000.0), (30, 15_000_000.0)];
+        for (years, expected) in cases {
+            assert_close(retirement_income_deduction(years), expected);
+        }
+    }
+
+    #[test]
+    fn lump_sum_tax_halves_the_income_after_deduction() {
+        // (2000万円 - 1500万円) / 2 = 250万円 が退職所得
+        assert_close(lump_sum_tax(20_000_000.0, 30), 152_500.0 * RECONSTRUCTION_TAX_FACTOR + 250_000.0);
+        // 控除の範囲内なら非課税
+        assert_close(lump_sum_tax(15_000_000.0, 30), 0.0);
+    }
+
+    #[test]
+    fn annual_tax_saving_across_brackets() {
+        // 同じ税率の範囲: 所得税の差 55,200円 と住民税 27,600円
+        assert_close(annual_tax_saving(4_000_000.0, 276_000.0), 55_200.0 * RECONSTRUCTION_TAX_FACTOR + 27_600.0);
+        // 20% → 10% の境界をまたぐ場合
+        assert_close(annual_tax_saving(3_400_000.0, 276_000.0), 37_600.0 * RECONSTRUCTION_TAX_FACTOR + 27_600.0);
+        // 課税所得を超える掛金は控除しきれない
+        assert_close(annual_tax_saving(100_000.0, 276_000.0), 5_000.0 * RECONSTRUCTION_TAX_FACTOR + 10_000.0);
+    }
+
+    // 拠出終了年齢の後は掛金も節税額もなくなる
+    #[test]
+    fn contributions_stop_at_end_age() {
+        let config = IdecoConfig {
+            monthly_contribution: Some(10_000.0),
+            contribution_end_age: 60,
+            payout_age: 62,
+            ..IdecoConfig::default()
+        };
+        let result = simulate_ideco(50_000.0, 0.0, PaymentTiming::Beginning, 58, &config);
+        assert_eq!(result.contribution_years, 2);
+        let balances: Vec<f64> = result.yearly.iter().map(|year| year.balance).collect();
+        assert_eq!(balances, vec![120_000.0, 240_000.0, 240_000.0, 240_000.0]);
+        assert!(result.yearly[1].tax_saving > 0.0);
+        assert_eq!((result.yearly[2].tax_saving, result.yearly[3].tax_saving), (0.0, 0.0));
+        assert_eq!(result.payout, 240_000.0);
+    }
+}
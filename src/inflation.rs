@@ -0,0 +1,70 @@
+// インフレ率: 一定の率、または年ごとの率（最後の率を以降の年にも使う）
+
+use crate::error::PlanError;
+
+#[derive(Debug, Clone)]
+pub struct Inflation {
+    annual_rates: Vec<f64>,
+}
+
+impl Inflation {
+    pub fn constant(rate: f64) -> Self {
+        Inflation {
+            annual_rates: vec![rate],
+        }
+    }
+
+    pub fn schedule(rates: Vec<f64>) -> Self {
+        Inflation { annual_rates: rates }
+    }
+
+    pub fn validate(&self) -> Result<(), PlanError> {
+        if self.annual_rates.is_empty() {
+            return Err(PlanError::new("inflation", "インフレ率を1つ以上指定してください"));
+        }
+        if let Some(rate) = self
+            .annual_rates
+            .iter()
+            .find(|rate| !rate.is_finite() || **rate <= -1.0)
+        {
+            return Err(PlanError::new("inflation", format!(
+                "インフレ率には-100%より大きい値を指定してください: {}%",
+                rate * 100.0
+            )));
+        }
+        Ok(())
+    }
+
+    // year 年目（0始まり）のインフレ率
+    pub fn rate_for_year(&self, year: usize) -> f64 {
+        let last = self.annual_rates.len().saturating_sub(1);
+        self.annual_rates.get(year.min(last)).copied().unwrap_or(0.0)
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.annual_rates.windows(2).all(|w| w[0] == w[1])
+    }
+
+    // 今を1としたときの months ヶ月後の物価水準（年の途中は月割りで複利）
+    pub fn price_level(&self, months: usize) -> f64 {
+        let mut level = 1.0;
+        for year in 0..months / 12 {
+            level *= 1.0 + self.rate_for_year(year);
+        }
+        let remaining = (months % 12) as f64 / 12.0;
+        level * (1.0 + self.rate_for_year(months / 12)).powf(remaining)
+    }
+
+    // 表示用の説明（例: "2.0%" / "3.0% → 2.5% → 2.0%"）
+    pub fn describe(&self) -> String {
+        if self.is_constant() {
+            format!("{:.1}%", self.rate_for_year(0) * 100.0)
+        } else {
+            self.annual_rates
+                .iter()
+                .map(|rate| format!("{:.1}%", rate * 100.0))
+                .collect::<Vec<_>>()
+                .join(" → ")
+        }
+    }
+}
@@ -1,106 +1,131 @@
-// 複利計算: 毎月の積立で目標金額に到達するための月額を計算
-fn calculate_monthly_investment_for_target(
-    target_amount: f64,
-    annual_rate: f64,
-    years: usize,
-) -> f64 {
-    let months = years * 12;
-    let monthly_rate = annual_rate / 12.0;
-
-    // 将来価値の年金現在価値の逆算
-    // FV = PMT × [(1 + r)^n - 1] / r
-    // PMT = FV × r / [(1 + r)^n - 1]
-
-    let denominator = ((1.0 + monthly_rate).powi(months as i32) - 1.0) / monthly_rate;
-    target_amount / denominator
-}
+mod cli;
+mod compounding;
+mod contribution;
+mod error;
+mod fees;
+mod historical;
+mod ideco;
+mod inflation;
+mod monte_carlo;
+mod nisa;
+mod plan;
+mod report;
+mod scenario;
+mod simulation;
+mod taxable;
+mod tvm;
+mod withdrawal;
+
+use cli::Command;
+use error::Error;
+use plan::MAX_YEARS;
+use report::{
+    contribution_label, format_months, format_yen, format_yen_real, profit_rate_label, required_rate_label, target_label,
+    REALISTIC_ANNUAL_RATE,
+};
+use simulation::simulate_index_investment;
+use tvm::PaymentTiming;
 
-// 実際にシミュレーション（年利固定）
-fn simulate_index_investment(
-    monthly_investment: f64,
-    annual_rate: f64,
-    years: usize,
-) -> Vec<f64> {
-    let months = years * 12;
-    let monthly_rate = annual_rate / 12.0;
-    let mut wealth = 0.0;
-    let mut yearly_wealth = Vec::with_capacity(years);
-
-    for month in 1..=months {
-        // 毎月の積立
-        wealth += monthly_investment;
-
-        // 月次の利息
-        wealth *= 1.0 + monthly_rate;
-
-        // 年末の資産を記録
-        if month % 12 == 0 {
-            yearly_wealth.push(wealth);
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("エラー: {}", error);
+        if error.is_usage() {
+            eprintln!("使い方は --help を参照してください");
         }
+        std::process::exit(2);
     }
-
-    yearly_wealth
 }
 
-fn format_yen(amount: f64) -> String {
-    if amount >= 100_000_000.0 {
-        format!("{:.2}億円", amount / 100_000_000.0)
-    } else if amount >= 10_000.0 {
-        format!("{:.1}万円", amount / 10_000.0)
-    } else {
-        format!("{:.0}円", amount)
-    }
-}
+fn run() -> Result<(), Error> {
+    let plan = match cli::parse_args(std::env::args().skip(1))? {
+        Command::Run(plan) => plan,
+        Command::Help => {
+            print!("{}", cli::HELP);
+            return Ok(());
+        }
+    };
 
-fn main() {
-    let target_amount = 100_000_000.0; // 目標1億円
-    let annual_rate = 0.05; // 年利5%
-    let current_monthly = 50_000.0; // 現在の月額投資
+    // 過去データは表示を始める前に読み込んでおく
+    let history = plan
+        .history_file
+        .as_deref()
+        .map(historical::load_csv)
+        .transpose()
+        .map_err(Error::History)?;
+
+    let annual_rate = plan.annual_rate;
+    let current_monthly = plan.current_monthly;
+    let periods = &plan.periods;
+    let longest_years = plan.longest_years();
 
     println!("╔══════════════════════════════════════════════════════════════╗");
-    println!("║        目標資産1億円達成シミュレーター                       ║");
-    println!("║        (インデックス投資 - 年利5%固定)                       ║");
+    println!("║        目標資産達成シミュレーター                            ║");
+    println!("║        (インデックス投資 - 年利{:>4.1}%固定)                    ║", annual_rate * 100.0);
     println!("╚══════════════════════════════════════════════════════════════╝\n");
 
-    println!("🎯 目標資産: {}", format_yen(target_amount));
+    if let Some(name) = &plan.name {
+        println!("📁 シナリオ: {}", name);
+    }
+    println!("🎯 目標資産: {}", target_label(&plan));
     println!("📊 想定年利: {:.1}% (インデックス投資の長期平均)", annual_rate * 100.0);
-    println!("💰 現在の月額投資: {}\n", format_yen(current_monthly));
+    println!("🔁 年利の種類: {}（実効年利 {:.2}% / 月次 {:.3}%）",
+             plan.rate_convention.describe(),
+             plan.rate_convention.effective_rate(annual_rate) * 100.0,
+             plan.rate_convention.monthly_rate(annual_rate) * 100.0);
+    if plan.initial_balance > 0.0 {
+        println!("🏦 初期資産: {}", format_yen(plan.initial_balance));
+    }
+    println!("💰 現在の月額投資: {}", format_yen(current_monthly));
+    if !plan.contribution.is_flat() {
+        println!("📈 積立額の推移: {}", contribution_label(&plan));
+    }
+    if plan.contribution_timing == PaymentTiming::End {
+        println!("🗓️ 積立のタイミング: {}（その月の運用の後に積立）", plan.contribution_timing.label());
+    }
+    println!();
 
-    // 期間ごとの必要月額を計算
-    let periods = vec![10, 15, 20, 25, 30];
+    if let Some(inflation) = &plan.inflation {
+        report::print_inflation_section(&plan, inflation);
+    }
 
+    // 期間ごとの必要月額を計算
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("📈 目標1億円達成に必要な毎月の投資額");
+    println!("📈 目標{}達成に必要な毎月の投資額", target_label(&plan));
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
-    println!("{:<8} {:<18} {:<18} {:<15} {:<12}",
-             "期間", "必要月額", "総投資額(元本)", "運用益", "達成可否");
-    println!("{}", "─".repeat(80));
+    println!("{:<8} {:<18} {:<18} {:<15} {:<10} {:<12}",
+             "期間", "必要月額", "総投資額(元本)", "運用益", "必要年利", "達成可否");
+    println!("{}", "─".repeat(90));
 
-    for &years in &periods {
-        let required_monthly = calculate_monthly_investment_for_target(
-            target_amount,
-            annual_rate,
-            years,
-        );
+    for &years in periods {
+        // インフレを考慮する場合は名目の目標額で計算
+        let target_amount = plan.target_at_year(years);
+        // 積立額のスケジュールを指定した場合は1年目の月額
+        let Some(required_monthly) = plan.required_monthly(target_amount, years) else {
+            println!("{:>6}年 {:>18} {:>18} {:>15}      {:>8} ❌ 積立期間がないため達成不可",
+                     years, "-", format_yen(plan.principal(years)), "-", required_rate_label(&plan, years));
+            continue;
+        };
 
-        let total_invested = required_monthly * (years * 12) as f64;
+        let total_invested = plan.initial_balance
+            + plan.contributions_with_start(required_monthly, years).iter().sum::<f64>();
         let profit = target_amount - total_invested;
-        let profit_rate = (profit / total_invested) * 100.0;
 
         // 現在の投資額で達成可能かチェック
-        let achievable = if required_monthly <= current_monthly {
+        let achievable = if required_monthly <= plan.base_monthly() {
             "✅ 達成可能"
         } else {
             "❌ 足りないよ！"
         };
 
-        println!("{:>6}年 {:>18} {:>18} {:>15} ({:.0}%) {}",
+        // 現在の投資額のままで達成するために必要な年利
+        println!("{:>6}年 {:>18} {:>18} {:>15} ({}) {:>8} {}",
             years,
             format_yen(required_monthly),
             format_yen(total_invested),
             format_yen(profit),
-            profit_rate,
+            profit_rate_label(profit, total_invested),
+            required_rate_label(&plan, years),
             achievable
         );
     }
@@ -113,28 +138,27 @@ fn main() {
              "期間", "最終資産", "総投資額(元本)", "運用益");
     println!("{}", "─".repeat(75));
 
-    for &years in &periods {
-        let yearly_wealth = simulate_index_investment(current_monthly, annual_rate, years);
-        let final_wealth = *yearly_wealth.last().unwrap_or(&0.0);
-        let total_invested = current_monthly * (years * 12) as f64;
+    for &years in periods {
+        let yearly_wealth = simulate_index_investment(plan.initial_balance, &plan.contributions(years), plan.simulation_rate(), plan.contribution_timing);
+        let final_wealth = yearly_wealth.last().copied().unwrap_or(plan.initial_balance);
+        let total_invested = plan.principal(years);
         let profit = final_wealth - total_invested;
-        let profit_rate = (profit / total_invested) * 100.0;
 
-        println!("{:>6}年 {:>18} {:>18} {:>18} ({:.0}%)",
+        println!("{:>6}年 {:>18} {:>18} {:>18} ({})",
             years,
-            format_yen(final_wealth),
+            format_yen_real(&plan, final_wealth, years * 12),
             format_yen(total_invested),
             format_yen(profit),
-            profit_rate
+            profit_rate_label(profit, total_invested)
         );
     }
 
-    // 年次推移を表示（現在の投資額で30年間）
+    // 年次推移を表示（現在の投資額で最長期間）
     println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("📈 年次資産推移（毎月{}で30年間投資した場合）", format_yen(current_monthly));
+    println!("📈 年次資産推移（{}で{}年間投資した場合）", contribution_label(&plan), longest_years);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
-    let yearly_wealth = simulate_index_investment(current_monthly, annual_rate, 30);
+    let yearly_wealth = simulate_index_investment(plan.initial_balance, &plan.contributions(longest_years), plan.simulation_rate(), plan.contribution_timing);
 
     println!("{:<8} {:<18} {:<18} {:<18}",
              "経過年数", "資産額", "投資額(累計)", "運用益");
@@ -142,46 +166,134 @@ fn main() {
 
     for (year, &wealth) in yearly_wealth.iter().enumerate() {
         let year_num = year + 1;
-        let total_invested = current_monthly * (year_num * 12) as f64;
+        let total_invested = plan.principal(year_num);
         let profit = wealth - total_invested;
 
-        // 5年ごと、または1億円到達時、または最終年に表示
-        if year_num % 5 == 0 || wealth >= target_amount || year_num == 30 {
-            let marker = if wealth >= target_amount { "🎯" } else { "  " };
+        // 5年ごと、または目標到達時、または最終年に表示
+        let reached = wealth >= plan.target_at_year(year_num);
+        if year_num % 5 == 0 || reached || year_num == longest_years {
+            let marker = if reached { "🎯" } else { "  " };
             println!("{}{:>6}年 {:>18} {:>18} {:>18}",
                 marker,
                 year_num,
-                format_yen(wealth),
+                format_yen_real(&plan, wealth, year_num * 12),
                 format_yen(total_invested),
                 format_yen(profit)
             );
         }
     }
 
+    if !plan.adjustments.is_empty() {
+        report::print_ledger_section(&plan);
+    }
+
+    if let Some(fees) = &plan.fees {
+        report::print_fee_section(&plan, fees);
+    }
+
+    if !plan.funds.is_empty() {
+        report::print_fund_comparison_section(&plan);
+    }
+
+    report::print_after_tax_section(&plan);
+
+    if let Some(config) = &plan.nisa {
+        report::print_nisa_section(&plan, config);
+    }
+
+    if let (Some(config), Some(age)) = (&plan.ideco, plan.age) {
+        report::print_ideco_section(&plan, config, age);
+    }
+
+    if let Some(config) = &plan.monte_carlo {
+        report::print_monte_carlo_section(&plan, config);
+    }
+
+    if let Some(series) = &history {
+        report::print_historical_section(&plan, series);
+    }
+
+    if let Some(config) = &plan.withdrawal {
+        report::print_withdrawal_section(&plan, config);
+        if !config.strategies.is_empty() {
+            report::print_withdrawal_strategy_section(&plan, config);
+        }
+        if let Some(success_rate) = config.success_rate {
+            report::print_safe_withdrawal_section(&plan, config, success_rate, history.as_ref());
+        }
+    }
+
     println!("\n\n╔══════════════════════════════════════════════════════════════╗");
     println!("║  まとめ                                                      ║");
     println!("╚══════════════════════════════════════════════════════════════╝");
-    println!("• 年利5%のインデックス投資（S&P500など）を想定");
+    println!("• 年利{:.1}%のインデックス投資（S&P500など）を想定", annual_rate * 100.0);
     println!("• 複利効果により、長期投資ほど有利");
-    println!("• 現在の投資額（{}）を継続した場合:", format_yen(current_monthly));
+    let current_label = if plan.contribution.is_flat() {
+        format_yen(current_monthly)
+    } else {
+        contribution_label(&plan)
+    };
+    if plan.initial_balance > 0.0 {
+        println!("• 初期資産{}に加えて、現在の投資額（{}）を継続した場合:",
+                 format_yen(plan.initial_balance),
+                 current_label);
+    } else {
+        println!("• 現在の投資額（{}）を継続した場合:", current_label);
+    }
 
-    let final_30y = *simulate_index_investment(current_monthly, annual_rate, 30).last().unwrap();
-    if final_30y >= target_amount {
-        println!("  → 30年で目標1億円を達成可能！ 🎉");
+    // 現在の計画のままで目標に到達するまでの期間
+    match plan.months_to_target() {
+        Some(0) => println!("  → すでに目標{}に到達しています", target_label(&plan)),
+        Some(months) => {
+            let age = plan.age.map_or(String::new(), |age| format!("（{}歳）", age + (months / 12) as u32));
+            println!("  → 目標{}まで{}{}", target_label(&plan), format_months(months), age);
+        }
+        None => println!("  → {}年以内には目標{}に到達しません", MAX_YEARS, target_label(&plan)),
+    }
+
+    let final_longest = yearly_wealth.last().copied().unwrap_or(plan.initial_balance);
+    let target_longest = plan.target_at_year(longest_years);
+    if final_longest >= target_longest {
+        println!("  → {}年で目標{}を達成可能！ 🎉", longest_years, target_label(&plan));
     } else {
-        let shortfall = target_amount - final_30y;
-        println!("  → 30年後は約{} (目標まであと{})",
-                 format_yen(final_30y),
+        let shortfall = target_longest - final_longest;
+        println!("  → {}年後は約{} (目標まであと{})",
+                 longest_years,
+                 format_yen_real(&plan, final_longest, longest_years * 12),
                  format_yen(shortfall));
 
+        // 現在の投資額のままで必要な年利
+        let target_principal = plan.principal(longest_years);
+        if let Some(rate) = plan.required_rate(target_longest, longest_years)
+            && target_principal < target_longest
+        {
+            let remark = if rate > REALISTIC_ANNUAL_RATE {
+                format!("（インデックス投資の長期平均{:.0}%を上回り、現実的ではありません）", REALISTIC_ANNUAL_RATE * 100.0)
+            } else {
+                String::new()
+            };
+            println!("  → 現在の投資額のまま{}年で達成するには年利{:.1}%が必要{}", longest_years, rate * 100.0, remark);
+        }
+
         // 必要な追加投資額を計算
-        let required_for_30y = calculate_monthly_investment_for_target(target_amount, annual_rate, 30);
-        let additional_needed = required_for_30y - current_monthly;
-        println!("  → 目標達成には月額あと{}の追加投資が必要", format_yen(additional_needed));
+        match plan.required_monthly(target_longest, longest_years) {
+            Some(required_for_longest) => {
+                let additional_needed = required_for_longest - plan.base_monthly();
+                if plan.contribution.is_flat() {
+                    println!("  → 目標達成には月額あと{}の追加投資が必要", format_yen(additional_needed));
+                } else if plan.base_monthly() != current_monthly {
+                    println!("  → 目標達成には同じ増やし方で最初の段階の月額をあと{}増やす必要", format_yen(additional_needed));
+                } else {
+                    println!("  → 目標達成には同じ増やし方で1年目の月額をあと{}増やす必要", format_yen(additional_needed));
+                }
+            }
+            None => println!("  → 積立期間がないため、月額を増やしても目標には届きません"),
+        }
     }
 
     println!("\n💡 ポイント:");
     println!("  • 早く始めるほど複利効果が大きい");
     println!("  • 長期投資（20年以上）が推奨");
     println!("  • 実際の年利は変動するため、余裕を持った計画を\n");
+    Ok(())
 }
@@ -0,0 +1,349 @@
+// モンテカルロシミュレーション: 月次リターンを確率分布から生成して多数の経路を試算
+
+use rand::distributions::Standard;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::error::PlanError;
+use crate::simulation::grow_with_contribution;
+use crate::tvm::PaymentTiming;
+
+// 報告するパーセンタイル (P5 / P25 / P50 / P75 / P95)
+pub const PERCENTILES: [f64; 5] = [5.0, 25.0, 50.0, 75.0, 95.0];
+
+// 月次リターンの分布
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReturnDistribution {
+    // 月次リターンそのものが正規分布に従う
+    Normal,
+    // 月次の対数リターンが正規分布に従う（資産がマイナスにならない）
+    LogNormal,
+}
+
+impl ReturnDistribution {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "normal" => Ok(ReturnDistribution::Normal),
+            "lognormal" => Ok(ReturnDistribution::LogNormal),
+            _ => Err(format!("分布は normal または lognormal を指定してください: {}", value)),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReturnDistribution::Normal => "正規分布",
+            ReturnDistribution::LogNormal => "対数正規分布",
+        }
+    }
+}
+
+// モンテカルロの設定
+#[derive(Debug, Clone)]
+pub struct MonteCarloConfig {
+    // 期待年利（None の場合は計画の想定年利を使う）
+    pub mean: Option<f64>,
+    // 年率ボラティリティ（標準偏差）
+    pub volatility: f64,
+    pub paths: usize,
+    // 乱数シード（None の場合は実行ごとに生成）
+    pub seed: Option<u64>,
+    pub distribution: ReturnDistribution,
+    // 必要月額を逆算するときに求める目標到達確率
+    pub confidence: f64,
+}
+
+impl Default for MonteCarloConfig {
+    fn default() -> Self {
+        MonteCarloConfig {
+            mean: None,
+            volatility: 0.15,
+            paths: 10_000,
+            seed: None,
+            distribution: ReturnDistribution::LogNormal,
+            confidence: 0.9,
+        }
+    }
+}
+
+impl MonteCarloConfig {
+    pub fn validate(&self) -> Result<(), PlanError> {
+        if let Some(mean) = self.mean
+            && (!mean.is_finite() || mean <= -1.0)
+        {
+            return Err(PlanError::new("monte_carlo.mean", "期待年利には-100%より大きい値を指定してください"));
+        }
+        if !self.volatility.is_finite() || self.volatility < 0.0 {
+            return Err(PlanError::new("monte_carlo.volatility", "ボラティリティには0以上の値を指定してください"));
+        }
+        if self.paths == 0 || self.paths > 1_000_000 {
+            return Err(PlanError::new("monte_carlo.paths", "試行回数は1〜1000000の範囲で指定してください"));
+        }
+        if !(self.confidence > 0.0 && self.confidence < 1.0) {
+            return Err(PlanError::new("monte_carlo.confidence", "到達確率は0%より大きく100%未満で指定してください"));
+        }
+        Ok(())
+    }
+}
+
+// 月次リターンの生成器
+pub struct ReturnGenerator {
+    rng: StdRng,
+    distribution: ReturnDistribution,
+    monthly_mean: f64,
+    monthly_volatility: f64,
+}
+
+impl ReturnGenerator {
+    pub fn new(annual_mean: f64, annual_volatility: f64, distribution: ReturnDistribution, seed: u64) -> Self {
+        // 確定シミュレーションと同じく、月次の期待リターンは年利 / 12
+        ReturnGenerator {
+            rng: StdRng::seed_from_u64(seed),
+            distribution,
+            monthly_mean: annual_mean / 12.0,
+            monthly_volatility: annual_volatility / 12.0_f64.sqrt(),
+        }
+    }
+
+    // 標準正規乱数（Box-Muller法）
+    fn standard_normal(&mut self) -> f64 {
+        let u1: f64 = 1.0 - self.rng.sample::<f64, _>(Standard); // (0, 1]
+        let u2: f64 = self.rng.sample(Standard);
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    pub fn next_return(&mut self) -> f64 {
+        let z = self.standard_normal();
+        match self.distribution {
+            // -100%を下回るリターンは資産ゼロとして扱う
+            ReturnDistribution::Normal => (self.monthly_mean + self.monthly_volatility * z).max(-1.0),
+            ReturnDistribution::LogNormal => {
+                // E[1 + r] = 1 + 月次期待リターン となるように対数の平均を補正
+                let sigma = self.monthly_volatility;
+                let mu = (1.0 + self.monthly_mean).ln() - sigma * sigma / 2.0;
+                (mu + sigma * z).exp() - 1.0
+            }
+        }
+    }
+}
+
+// シミュレーション結果: yearly_wealth[年][経路] に年末資産を格納
+pub struct MonteCarloResult {
+    pub seed: u64,
+    pub yearly_wealth: Vec<Vec<f64>>,
+    // 経路ごとに目標額を初めて上回った月（1始まり）。期間内に到達しなければ None
+    pub first_crossing_month: Vec<Option<usize>>,
+}
+
+impl MonteCarloResult {
+    pub fn paths(&self) -> usize {
+        self.first_crossing_month.len()
+    }
+
+    // 指定した月数までに一度でも目標額に到達した確率
+    pub fn probability_reached_by(&self, months: usize) -> f64 {
+        if self.paths() == 0 {
+            return 0.0;
+        }
+        let reached = self
+            .first_crossing_month
+            .iter()
+            .filter(|m| matches!(m, Some(m) if *m <= months))
+            .count();
+        reached as f64 / self.paths() as f64
+    }
+
+    // 指定した年の年末に資産が目標額以上である確率
+    pub fn probability_above_at_year(&self, year: usize, target_amount: f64) -> f64 {
+        match self.yearly_wealth.get(year.wrapping_sub(1)) {
+            Some(values) if !values.is_empty() => {
+                values.iter().filter(|&&w| w >= target_amount).count() as f64 / values.len() as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    // 目標に到達した経路だけを対象にした、初到達月のパーセンタイル
+    pub fn crossing_month_percentiles(&self) -> Option<[f64; 5]> {
+        let mut months: Vec<f64> = self
+            .first_crossing_month
+            .iter()
+            .flatten()
+            .map(|&m| m as f64)
+            .collect();
+        if months.is_empty() {
+            return None;
+        }
+        months.sort_by(|a, b| a.total_cmp(b));
+        Some(PERCENTILES.map(|p| percentile(&months, p)))
+    }
+
+    // 初到達した年ごとの経路数（添字0が1年目）
+    pub fn crossing_year_histogram(&self) -> Vec<usize> {
+        let mut histogram = vec![0; self.yearly_wealth.len()];
+        for month in self.first_crossing_month.iter().flatten() {
+            histogram[(month - 1) / 12] += 1;
+        }
+        histogram
+    }
+
+    // 各年のパーセンタイル資産額
+    pub fn yearly_percentiles(&self) -> Vec<[f64; 5]> {
+        self.yearly_wealth
+            .iter()
+            .map(|values| {
+                let mut sorted = values.clone();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                PERCENTILES.map(|p| percentile(&sorted, p))
+            })
+            .collect()
+    }
+}
+
+// 確率的なリターンでシミュレーション
+pub fn simulate_monte_carlo(
+    initial_balance: f64,
+    // 各月の積立額（contributions[0] が1ヶ月目）
+    contributions: &[f64],
+    annual_rate: f64,
+    timing: PaymentTiming,
+    // 各月の目標額（monthly_targets[0] が1ヶ月目）。インフレを考慮すると月ごとに変わる
+    monthly_targets: &[f64],
+    config: &MonteCarloConfig,
+) -> MonteCarloResult {
+    let seed = config.seed.unwrap_or_else(rand::random);
+    let mean = config.mean.unwrap_or(annual_rate);
+    let mut generator = ReturnGenerator::new(mean, config.volatility, config.distribution, seed);
+    let mut yearly_wealth = vec![Vec::with_capacity(config.paths); contributions.len() / 12];
+    let mut first_crossing_month = Vec::with_capacity(config.paths);
+
+    for _ in 0..config.paths {
+        let mut wealth = initial_balance;
+        let mut crossed = None;
+
+        for (index, &contribution) in contributions.iter().enumerate() {
+            let month = index + 1;
+
+            // 毎月の積立と月次のリターン
+            wealth = grow_with_contribution(wealth, contribution, generator.next_return(), timing);
+
+            // 目標額に初めて到達した月を記録
+            if crossed.is_none() && wealth >= monthly_targets[month - 1] {
+                crossed = Some(month);
+            }
+
+            // 年末の資産を記録
+            if month % 12 == 0 {
+                yearly_wealth[month / 12 - 1].push(wealth);
+            }
+        }
+
+        first_crossing_month.push(crossed);
+    }
+
+    MonteCarloResult {
+        seed,
+        yearly_wealth,
+        first_crossing_month,
+    }
+}
+
+// 指定した確率で期末に目標額以上となる、最小の毎月の投資額を求める
+//
+// 1年目の月額を c とすると各月の積立額は c × unit[m] + fixed[m] と表せ（積立額のスケジュール）、
+// 初期資産を P とすると各経路の期末資産は P × G + c × A + B
+// （G は経路ごとの累積リターン、A・B は unit・fixed を積み立てた場合の期末資産）と c について線形になる。
+// そこで経路ごとに必要な月額 (target - P × G - B) / A を求め、
+// その confidence 分位点を取れば、全経路を再シミュレーションせずに逆算できる。
+// 同じシードを使えば期間ごとの結果は同じ乱数列に基づく。
+// 積立期間がなく、月額を増やしても必要な割合の経路が届かない場合は None
+pub fn required_monthly_for_confidence(
+    target_amount: f64,
+    initial_balance: f64,
+    // 各月の (1年目の月額1円あたりの積立額, 月額によらない積立額)
+    contributions: &[(f64, f64)],
+    annual_rate: f64,
+    timing: PaymentTiming,
+    seed: u64,
+    config: &MonteCarloConfig,
+) -> Option<f64> {
+    let mean = config.mean.unwrap_or(annual_rate);
+    let mut generator = ReturnGenerator::new(mean, config.volatility, config.distribution, seed);
+    let mut required = Vec::with_capacity(config.paths);
+
+    for _ in 0..config.paths {
+        // 1円を運用した場合と、unit・fixed を積み立てた場合の期末資産
+        let mut growth = 1.0;
+        let mut annuity_factor = 0.0;
+        let mut fixed_wealth = 0.0;
+        for &(unit, fixed) in contributions {
+            let monthly_return = generator.next_return();
+            growth *= 1.0 + monthly_return;
+            annuity_factor = grow_with_contribution(annuity_factor, unit, monthly_return, timing);
+            fixed_wealth = grow_with_contribution(fixed_wealth, fixed, monthly_return, timing);
+        }
+
+        let shortfall = target_amount - initial_balance * growth - fixed_wealth;
+        required.push(if shortfall <= 0.0 {
+            0.0
+        } else if annuity_factor > 0.0 {
+            shortfall / annuity_factor
+        } else {
+            f64::INFINITY
+        });
+    }
+
+    required.sort_by(|a, b| a.total_cmp(b));
+    let index = ((config.confidence * config.paths as f64).ceil() as usize).clamp(1, config.paths) - 1;
+    Some(required[index]).filter(|required| required.is_finite())
+}
+
+// ソート済みの値から線形補間でパーセンタイルを求める
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p / 100.0) * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let weight = rank - lower as f64;
+    sorted[lower] * (1.0 - weight) + sorted[upper] * weight
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 求めた月額で同じシードのシミュレーションをやり直すと、指定した割合以上の経路が目標に届き、
+    // それより少し少ない月額では届かないこと
+    #[test]
+    fn required_monthly_reaches_target_with_confidence() {
+        let target_amount = 30_000_000.0;
+        let years = 20;
+        // 2年目から毎年3%増額し、毎年6月にボーナスから10万円を上乗せする積立
+        let contributions: Vec<(f64, f64)> = (0..years * 12)
+            .map(|month| (1.03f64.powi(month as i32 / 12), if month % 12 == 5 { 100_000.0 } else { 0.0 }))
+            .collect();
+        let config = MonteCarloConfig {
+            paths: 2_000,
+            seed: Some(42),
+            confidence: 0.9,
+            ..MonteCarloConfig::default()
+        };
+        let targets = vec![target_amount; years * 12];
+        let probability = |monthly: f64, timing: PaymentTiming| {
+            let schedule: Vec<f64> = contributions.iter().map(|&(unit, fixed)| monthly * unit + fixed).collect();
+            simulate_monte_carlo(1_000_000.0, &schedule, 0.05, timing, &targets, &config)
+                .probability_above_at_year(years, target_amount)
+        };
+
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            let required =
+                required_monthly_for_confidence(target_amount, 1_000_000.0, &contributions, 0.05, timing, 42, &config).unwrap();
+            // 分位点の経路はちょうど目標額になるので、丸め誤差の分だけ上乗せして確認する
+            assert!(probability(required * (1.0 + 1e-9), timing) >= 0.9, "{:?}: {}", timing, required);
+            assert!(probability(required * 0.99, timing) < 0.9, "{:?}: {}", timing, required);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// 新NISA: つみたて投資枠・成長投資枠の年間上限と生涯投資枠を考慮した積立
+//
+// 毎月の積立は つみたて投資枠 → 成長投資枠 → 課税口座 の順に割り当てる。
+// 年間投資枠は1月に戻る（シミュレーションは1月開始とする）。生涯投資枠は簿価で管理し、
+// 積立期間中は売却しないため枠の再利用は考えない。
+
+use crate::error::PlanError;
+use crate::simulation::monthly_step;
+use crate::taxable::{TaxConfig, TaxableAccount};
+use crate::tvm::PaymentTiming;
+
+#[derive(Debug, Clone)]
+pub struct NisaConfig {
+    pub tsumitate_annual_limit: f64,
+    pub growth_annual_limit: f64,
+    pub lifetime_limit: f64,
+    // 生涯投資枠のうち成長投資枠で使える上限
+    pub growth_lifetime_limit: f64,
+    // 成長投資枠でも積み立てるか
+    pub use_growth_frame: bool,
+    // すでに使っている生涯投資枠（簿価）
+    pub used_lifetime: f64,
+}
+
+impl Default for NisaConfig {
+    fn default() -> Self {
+        NisaConfig {
+            tsumitate_annual_limit: 1_200_000.0,
+            growth_annual_limit: 2_400_000.0,
+            lifetime_limit: 18_000_000.0,
+            growth_lifetime_limit: 12_000_000.0,
+            use_growth_frame: true,
+            used_lifetime: 0.0,
+        }
+    }
+}
+
+impl NisaConfig {
+    pub fn validate(&self) -> Result<(), PlanError> {
+        if !self.used_lifetime.is_finite() || self.used_lifetime < 0.0 {
+            return Err(PlanError::new("nisa.used", "使用済みの生涯投資枠には0以上の金額を指定してください"));
+        }
+        if self.used_lifetime > self.lifetime_limit {
+            return Err(PlanError::new("nisa.used", "使用済みの生涯投資枠が上限の1800万円を超えています"));
+        }
+        Ok(())
+    }
+}
+
+// NISA口座の残高と枠の使用状況
+#[derive(Debug, Clone, Default)]
+struct NisaAccount {
+    value: f64,
+    cost_basis: f64,
+    lifetime_used: f64,
+    growth_lifetime_used: f64,
+    tsumitate_this_year: f64,
+    growth_this_year: f64,
+}
+
+impl NisaAccount {
+    // 枠に収まる分だけ買い付け、残りの金額を返す
+    fn deposit(&mut self, amount: f64, config: &NisaConfig) -> f64 {
+        let mut rest = amount;
+
+        // つみたて投資枠
+        let room = (config.tsumitate_annual_limit - self.tsumitate_this_year)
+            .min(config.lifetime_limit - self.lifetime_used)
+            .max(0.0);
+        let tsumitate = rest.min(room);
+        self.tsumitate_this_year += tsumitate;
+        rest -= tsumitate;
+
+        // 成長投資枠
+        let mut growth = 0.0;
+        if config.use_growth_frame {
+            let room = (config.growth_annual_limit - self.growth_this_year)
+                .min(config.growth_lifetime_limit - self.growth_lifetime_used)
+                .min(config.lifetime_limit - self.lifetime_used - tsumitate)
+                .max(0.0);
+            growth = rest.min(room);
+            self.growth_this_year += growth;
+            self.growth_lifetime_used += growth;
+            rest -= growth;
+        }
+
+        let invested = tsumitate + growth;
+        self.value += invested;
+        self.cost_basis += invested;
+        self.lifetime_used += invested;
+        rest
+    }
+
+    fn start_new_year(&mut self) {
+        self.tsumitate_this_year = 0.0;
+        self.growth_this_year = 0.0;
+    }
+}
+
+// 年末時点の口座別の内訳
+#[derive(Debug, Clone)]
+pub struct NisaYear {
+    pub nisa_value: f64,
+    pub nisa_cost_basis: f64,
+    pub taxable: TaxableAccount,
+}
+
+impl NisaYear {
+    pub fn total(&self) -> f64 {
+        self.nisa_value + self.taxable.value()
+    }
+
+    // すべて売却した場合の手取り（NISA口座は非課税）
+    pub fn after_tax_total(&self) -> f64 {
+        self.total() - self.taxable.tax_if_liquidated()
+    }
+}
+
+pub struct NisaResult {
+    pub yearly: Vec<NisaYear>,
+    // 生涯投資枠を使い切った月（1始まり）
+    pub lifetime_filled_month: Option<usize>,
+}
+
+// NISAの枠を考慮してシミュレーション
+pub fn simulate_with_nisa(
+    initial_balance: f64,
+    // 各月の積立額（contributions[0] が1ヶ月目）
+    contributions: &[f64],
+    annual_rate: f64,
+    timing: PaymentTiming,
+    config: &NisaConfig,
+    tax: &TaxConfig,
+) -> NisaResult {
+    let monthly_rate = annual_rate / 12.0;
+    let mut nisa = NisaAccount {
+        lifetime_used: config.used_lifetime,
+        ..NisaAccount::default()
+    };
+    let mut taxable = tax.new_account();
+    let mut yearly = Vec::with_capacity(contributions.len() / 12);
+    let mut lifetime_filled_month = None;
+
+    // 初期資産はすでに課税口座で保有しているものとみなす（取得価額 = 初期資産）
+    taxable.deposit(initial_balance);
+
+    for (index, &contribution) in contributions.iter().enumerate() {
+        let month = index + 1;
+
+        // 毎月の積立（枠を超えた分は課税口座へ）と月次の利息
+        monthly_step(
+            &mut (&mut nisa, &mut taxable),
+            timing,
+            |(nisa, taxable)| {
+                let overflow = nisa.deposit(contribution, config);
+                taxable.deposit(overflow);
+            },
+            |(nisa, taxable)| {
+                nisa.value *= 1.0 + monthly_rate;
+                taxable.grow(monthly_rate);
+            },
+        );
+
+        if lifetime_filled_month.is_none() && nisa.lifetime_used >= config.lifetime_limit {
+            lifetime_filled_month = Some(month);
+        }
+
+        // 年末の資産を記録し、年間投資枠をリセット
+        if month % 12 == 0 {
+            yearly.push(NisaYear {
+                nisa_value: nisa.value,
+                nisa_cost_basis: nisa.cost_basis,
+                taxable: taxable.clone(),
+            });
+            nisa.start_new_year();
+        }
+    }
+
+    NisaResult {
+        yearly,
+        lifetime_filled_month,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deposit_fills_frames_in_order() {
+        let config = NisaConfig::default();
+        let mut account = NisaAccount::default();
+        // つみたて投資枠120万円 → 成長投資枠 の順に埋める
+        assert_eq!(account.deposit(3_000_000.0, &config), 0.0);
+        assert_eq!((account.tsumitate_this_year, account.growth_this_year), (1_200_000.0, 1_800_000.0));
+        // 年間投資枠を超えた分は課税口座へ
+        assert_eq!(account.deposit(3_000_000.0, &config), 2_400_000.0);
+        assert_eq!(account.growth_this_year, 2_400_000.0);
+        // 年が変わると年間投資枠は戻るが、生涯投資枠は戻らない
+        account.start_new_year();
+        assert_eq!(account.deposit(100_000.0, &config), 0.0);
+        assert_eq!(account.lifetime_used, 3_700_000.0);
+
+        // 成長投資枠を使わない場合は、つみたて投資枠を超えた分がすべて課税口座へ
+        let config = NisaConfig { use_growth_frame: false, ..NisaConfig::default() };
+        assert_eq!(NisaAccount::default().deposit(2_000_000.0, &config), 800_000.0);
+    }
+
+    #[test]
+    fn deposit_stops_at_lifetime_limit() {
+        let config = NisaConfig::default();
+        let mut account = NisaAccount { lifetime_used: 17_000_000.0, ..NisaAccount::default() };
+        assert_eq!(account.deposit(500_000.0, &config), 0.0);
+        // 生涯投資枠の残り50万円だけ買い付ける
+        assert_eq!(account.deposit(1_000_000.0, &config), 500_000.0);
+        assert_eq!(account.lifetime_used, 18_000_000.0);
+        assert_eq!(account.growth_this_year, 0.0);
+
+        // 成長投資枠は生涯1200万円まで
+        let mut account = NisaAccount { growth_lifetime_used: 11_900_000.0, lifetime_used: 11_900_000.0, ..NisaAccount::default() };
+        assert_eq!(account.deposit(2_000_000.0, &config), 700_000.0);
+        assert_eq!(account.growth_lifetime_used, 12_000_000.0);
+    }
+}
@@ -0,0 +1,364 @@
+use std::ops::Deref;
+use std::path::PathBuf;
+
+use crate::compounding::RateConvention;
+use crate::contribution::{self, Adjustment, ContributionSchedule};
+use crate::error::PlanError;
+use crate::fees::{Fund, FundFees};
+use crate::ideco::IdecoConfig;
+use crate::inflation::Inflation;
+use crate::monte_carlo::MonteCarloConfig;
+use crate::nisa::NisaConfig;
+use crate::simulation::{
+    calculate_monthly_investment_for_target, calculate_months_to_target, calculate_required_annual_rate,
+    calculate_starting_monthly_for_target, months_to_reach,
+};
+use crate::taxable::TaxConfig;
+use crate::tvm::PaymentTiming;
+use crate::withdrawal::WithdrawalConfig;
+
+// 目標到達までの月数を探す上限（年）
+pub const MAX_YEARS: usize = 100;
+
+// 想定年利の上限（これより大きいと必要月額が1円未満になり、計算結果に意味がなくなる）
+const MAX_ANNUAL_RATE: f64 = 1.0;
+
+// シミュレーションの前提条件（投資計画）
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub name: Option<String>,
+    pub target_amount: f64,
+    pub annual_rate: f64,
+    // 想定年利が名目年利か実効年利か、名目年利の複利の頻度
+    pub rate_convention: RateConvention,
+    pub current_monthly: f64,
+    // すでに保有している資産（初期資産）
+    pub initial_balance: f64,
+    // 毎月の積立額の増やし方（current_monthly が1年目の月額）
+    pub contribution: ContributionSchedule,
+    // ボーナス月・臨時の積立・休止期間など、月ごとの積立額の調整
+    pub adjustments: Vec<Adjustment>,
+    // 毎月の積立のタイミング（月初なら積み立ててからその月の運用、月末なら運用の後に積立）
+    pub contribution_timing: PaymentTiming,
+    pub periods: Vec<usize>,
+    // 指定された場合は目標額を今の価値とみなし、各金額を実質値でも表示する
+    pub inflation: Option<Inflation>,
+    // 現在の年齢（iDeCoなど年齢に依存する計算で使用）
+    pub age: Option<u32>,
+    // 指定された場合はモンテカルロシミュレーションも実行する
+    pub monte_carlo: Option<MonteCarloConfig>,
+    // 指定された場合は過去の指数データ（CSV）で積立を再現する
+    pub history_file: Option<PathBuf>,
+    // 指定された場合は新NISAの枠を考慮して口座ごとの内訳を表示する
+    pub nisa: Option<NisaConfig>,
+    // 課税口座の税制
+    pub tax: TaxConfig,
+    // 指定された場合はiDeCoの節税額と受取額を表示する
+    pub ideco: Option<IdecoConfig>,
+    // 指定された場合は信託報酬などの手数料を差し引いた結果と手数料の総額を表示する
+    pub fees: Option<FundFees>,
+    // 同じ積立計画で比較するファンド（空の場合は比較しない）
+    pub funds: Vec<Fund>,
+    // 指定された場合は積立期間の後の取り崩し期間もシミュレーションする
+    pub withdrawal: Option<WithdrawalConfig>,
+}
+
+impl Default for Plan {
+    fn default() -> Self {
+        Plan {
+            name: None,
+            target_amount: 100_000_000.0, // 目標1億円
+            annual_rate: 0.05,            // 年利5%
+            rate_convention: RateConvention::default(), // 月複利の名目年利
+            current_monthly: 50_000.0,    // 現在の月額投資
+            initial_balance: 0.0,         // 初期資産なし
+            contribution: ContributionSchedule::Flat,
+            adjustments: Vec::new(),
+            contribution_timing: PaymentTiming::Beginning, // 月初に積立
+            periods: vec![10, 15, 20, 25, 30],
+            inflation: None,
+            age: None,
+            monte_carlo: None,
+            history_file: None,
+            nisa: None,
+            tax: TaxConfig::default(),
+            ideco: None,
+            fees: None,
+            funds: Vec::new(),
+            withdrawal: None,
+        }
+    }
+}
+
+impl Plan {
+    // 最長の期間（年次推移やまとめで使用）
+    pub fn longest_years(&self) -> usize {
+        *self.periods.iter().max().unwrap_or(&30)
+    }
+
+    // 今を1としたときの months ヶ月後の物価水準
+    pub fn price_level(&self, months: usize) -> f64 {
+        self.inflation
+            .as_ref()
+            .map_or(1.0, |inflation| inflation.price_level(months))
+    }
+
+    // months ヶ月後の名目の目標額（インフレを考慮しない場合は常に target_amount）
+    pub fn target_at_month(&self, months: usize) -> f64 {
+        self.target_amount * self.price_level(months)
+    }
+
+    pub fn target_at_year(&self, years: usize) -> f64 {
+        self.target_at_month(years * 12)
+    }
+
+    // 1ヶ月目から years 年目の最後の月までの名目の目標額
+    pub fn monthly_targets(&self, years: usize) -> Vec<f64> {
+        (1..=years * 12).map(|month| self.target_at_month(month)).collect()
+    }
+
+    // 積立額のスケジュールの基準の月額（必要月額はこの月額として求める）
+    pub fn base_monthly(&self) -> f64 {
+        self.contribution.base_monthly(self.current_monthly)
+    }
+
+    // 基準の月額を start としたときの、スケジュールどおりの各月の積立額（月ごとの調整を含まない）
+    pub fn scheduled_contributions(&self, start: f64, years: usize) -> Vec<f64> {
+        (0..years * 12)
+            .map(|month| {
+                let year = month / 12;
+                let age = self.age.map(|age| age + year as u32);
+                self.contribution
+                    .monthly_for_year(start, self.current_monthly, year, age)
+            })
+            .collect()
+    }
+
+    // 基準の月額を start としたときの、years 年間の各月の積立額
+    pub fn contributions_with_start(&self, start: f64, years: usize) -> Vec<f64> {
+        let mut contributions = self.scheduled_contributions(start, years);
+        contribution::apply_adjustments(&mut contributions, &self.adjustments);
+        contributions
+    }
+
+    // years 年間の各月の積立額（contributions[0] が1ヶ月目）
+    pub fn contributions(&self, years: usize) -> Vec<f64> {
+        self.contributions_with_start(self.base_monthly(), years)
+    }
+
+    // years 年間の投資元本（初期資産 + 積立額の累計）
+    pub fn principal(&self, years: usize) -> f64 {
+        self.initial_balance + self.contributions(years).iter().sum::<f64>()
+    }
+
+    // years 年後に target_amount に到達するための基準の月額（積立額のスケジュールを考慮）
+    // 積立期間がなく、月額をいくら増やしても届かない場合は None
+    pub fn required_monthly(&self, target_amount: f64, years: usize) -> Option<f64> {
+        if self.contribution.is_flat() && self.adjustments.is_empty() {
+            return calculate_monthly_investment_for_target(
+                target_amount,
+                self.initial_balance,
+                self.simulation_rate(),
+                years,
+                self.contribution_timing,
+            );
+        }
+        calculate_starting_monthly_for_target(
+            target_amount,
+            self.initial_balance,
+            self.simulation_rate(),
+            self.contribution_timing,
+            |start| self.contributions_with_start(start, years),
+        )
+    }
+
+    // 現在の積立計画のまま years 年後に target_amount に到達するために必要な年利（想定年利と同じ種類の年利）
+    pub fn required_rate(&self, target_amount: f64, years: usize) -> Option<f64> {
+        calculate_required_annual_rate(
+            target_amount,
+            self.initial_balance,
+            &self.contributions(years),
+            self.contribution_timing,
+        )
+        .map(|rate| self.rate_convention.annual_rate(rate / 12.0))
+    }
+
+    // 入力された年利を、シミュレーションに渡す年利（月複利の名目年利）に換算
+    pub fn compounded_rate(&self, annual_rate: f64) -> f64 {
+        self.rate_convention.monthly_compounded(annual_rate)
+    }
+
+    // シミュレーションに渡す想定年利
+    pub fn simulation_rate(&self) -> f64 {
+        self.compounded_rate(self.annual_rate)
+    }
+
+    // モンテカルロの期待年利も想定年利と同じく換算した設定
+    pub fn simulation_monte_carlo(&self, config: &MonteCarloConfig) -> MonteCarloConfig {
+        MonteCarloConfig {
+            mean: config.mean.map(|mean| self.compounded_rate(mean)),
+            ..config.clone()
+        }
+    }
+
+    // 現在の積立計画で目標額に到達するまでの月数（MAX_YEARS 年以内に到達しなければ None）
+    // 一定額で調整もインフレもなければ公式で、それ以外は毎月の資産を順に計算して求める
+    pub fn months_to_target(&self) -> Option<usize> {
+        let months = if self.contribution.is_flat() && self.adjustments.is_empty() && self.inflation.is_none() {
+            calculate_months_to_target(
+                self.target_amount,
+                self.initial_balance,
+                self.current_monthly,
+                self.simulation_rate(),
+                self.contribution_timing,
+            )
+        } else {
+            months_to_reach(
+                self.initial_balance,
+                &self.contributions(MAX_YEARS),
+                self.simulation_rate(),
+                self.contribution_timing,
+                &self.monthly_targets(MAX_YEARS),
+            )
+        };
+        months.filter(|&months| months <= MAX_YEARS * 12)
+    }
+
+    // months ヶ月後の名目の金額を今の価値に換算
+    pub fn to_real(&self, amount: f64, months: usize) -> f64 {
+        amount / self.price_level(months)
+    }
+
+    // 値の妥当性チェック。エラーメッセージには問題のある項目名を含める
+    pub fn validate(&self) -> Result<(), PlanError> {
+        if !self.target_amount.is_finite() || self.target_amount <= 0.0 {
+            return Err(PlanError::new("target", "目標資産額には正の金額を指定してください"));
+        }
+        if !self.current_monthly.is_finite() || self.current_monthly < 0.0 {
+            return Err(PlanError::new("monthly", "毎月の投資額には0以上の金額を指定してください"));
+        }
+        if !self.initial_balance.is_finite() || self.initial_balance < 0.0 {
+            return Err(PlanError::new("initial", "初期資産には0以上の金額を指定してください"));
+        }
+        if !self.annual_rate.is_finite() || self.annual_rate <= -1.0 || self.annual_rate > MAX_ANNUAL_RATE {
+            return Err(PlanError::new("rate", "年利には-100%より大きく100%以下の値を指定してください"));
+        }
+        self.rate_convention.validate()?;
+        if self.periods.is_empty() {
+            return Err(PlanError::new("years", "少なくとも1つの期間を指定してください"));
+        }
+        if let Some(&years) = self.periods.iter().find(|&&y| y == 0 || y > 100) {
+            return Err(PlanError::new("years", format!("期間は1〜100年の範囲で指定してください: {}", years)));
+        }
+        if let Some(age) = self.age
+            && !(15..=100).contains(&age)
+        {
+            return Err(PlanError::new("age", format!("年齢は15〜100歳の範囲で指定してください: {}", age)));
+        }
+        self.contribution.validate(self.age)?;
+        for adjustment in &self.adjustments {
+            adjustment.validate()?;
+        }
+        if let Some(inflation) = &self.inflation {
+            inflation.validate()?;
+        }
+        if let Some(monte_carlo) = &self.monte_carlo {
+            monte_carlo.validate()?;
+        }
+        if let Some(nisa) = &self.nisa {
+            nisa.validate()?;
+        }
+        self.tax.validate()?;
+        if let Some(ideco) = &self.ideco {
+            ideco.validate(self.age, self.current_monthly)?;
+        }
+        if let Some(fees) = &self.fees {
+            fees.validate("fees")?;
+        }
+        if let Some(withdrawal) = &self.withdrawal {
+            withdrawal.validate()?;
+        }
+        for (index, fund) in self.funds.iter().enumerate() {
+            fund.validate(&format!("funds[{}]", index))?;
+        }
+        Ok(())
+    }
+
+    // 妥当性を確認した計画に変換する
+    pub fn into_valid(self) -> Result<ValidPlan, PlanError> {
+        self.validate()?;
+        Ok(ValidPlan(self))
+    }
+}
+
+// 妥当性を確認済みの計画。シミュレーションはこの型から行う
+#[derive(Debug, Clone)]
+pub struct ValidPlan(Plan);
+
+impl Deref for ValidPlan {
+    type Target = Plan;
+
+    fn deref(&self) -> &Plan {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 計算結果に意味のない値は、問題のある項目名とともに拒否すること
+    #[test]
+    fn into_valid_rejects_degenerate_values() {
+        let cases = [
+            (Plan { annual_rate: -1.0, ..Plan::default() }, "rate"),
+            (Plan { annual_rate: f64::NAN, ..Plan::default() }, "rate"),
+            (Plan { annual_rate: 10.0, ..Plan::default() }, "rate"),
+            (Plan { target_amount: 0.0, ..Plan::default() }, "target"),
+            (Plan { current_monthly: -1.0, ..Plan::default() }, "monthly"),
+            (Plan { periods: vec![10, 0], ..Plan::default() }, "years"),
+        ];
+        for (plan, field) in cases {
+            assert_eq!(plan.into_valid().unwrap_err().field, field);
+        }
+        assert!(Plan { annual_rate: 0.0, ..Plan::default() }.into_valid().is_ok());
+        assert!(Plan { annual_rate: -0.05, ..Plan::default() }.into_valid().is_ok());
+    }
+
+    // 現在の月額が0の年齢別の積立は、最初の0でない段階の月額を基準に全体を拡大・縮小すること
+    #[test]
+    fn age_tiers_scale_from_the_first_non_zero_tier() {
+        let plan = Plan {
+            current_monthly: 0.0,
+            age: Some(30),
+            contribution: ContributionSchedule::AgeTiers(vec![
+                contribution::AgeTier { age: 32, monthly: 0.0 },
+                contribution::AgeTier { age: 35, monthly: 40_000.0 },
+                contribution::AgeTier { age: 45, monthly: 60_000.0 },
+            ]),
+            ..Plan::default()
+        };
+        assert_eq!(plan.base_monthly(), 40_000.0);
+
+        let contributions = plan.contributions(20);
+        assert_eq!(contributions[0], 0.0);
+        assert_eq!(contributions[5 * 12], 40_000.0);
+        assert_eq!(contributions[15 * 12], 60_000.0);
+
+        // 基準の月額を2倍にすると、それぞれの段階も2倍になる（段階の前は0のまま）
+        let doubled = plan.contributions_with_start(80_000.0, 20);
+        assert_eq!(doubled[0], 0.0);
+        assert_eq!(doubled[5 * 12], 80_000.0);
+        assert_eq!(doubled[15 * 12], 120_000.0);
+
+        // 必要月額は最初の段階の月額として求まり、その月額で積み立てると目標に届く
+        let required = plan.required_monthly(30_000_000.0, 20).unwrap();
+        let wealth = crate::simulation::simulate_index_investment(
+            plan.initial_balance,
+            &plan.contributions_with_start(required, 20),
+            plan.simulation_rate(),
+            plan.contribution_timing,
+        );
+        assert!((wealth.last().unwrap() - 30_000_000.0).abs() < 1e-3, "{}", wealth.last().unwrap());
+    }
+}
@@ -0,0 +1,1007 @@
+// 結果の表示
+
+use crate::fees::{self, FundFees};
+use crate::historical::{self, ReturnSeries, YearMonth};
+use crate::ideco::{self, IdecoConfig};
+use crate::inflation::Inflation;
+use crate::monte_carlo::{self, MonteCarloConfig, MonteCarloResult, ReturnGenerator};
+use crate::nisa::{self, NisaConfig};
+use crate::plan::Plan;
+use crate::contribution::{Adjustment, ContributionSchedule};
+use crate::simulation::{simulate_index_investment, simulate_monthly_wealth};
+use crate::taxable;
+use crate::withdrawal::{self, StrategyParams, WithdrawalConfig, WithdrawalStrategy};
+
+pub fn format_yen(amount: f64) -> String {
+    // 無限大や NaN は金額として表示しない（呼び出し側で「達成不可」などと表示する）
+    debug_assert!(amount.is_finite(), "format_yen: {}", amount);
+    if !amount.is_finite() {
+        return "-".to_string();
+    }
+    // マイナスの金額（マイナスの年利での運用益など）は絶対値を表示して符号を付ける
+    if amount < 0.0 {
+        let formatted = format_yen(-amount);
+        return if formatted == "0円" { formatted } else { format!("-{}", formatted) };
+    }
+    if amount >= 100_000_000.0 {
+        format!("{:.2}億円", amount / 100_000_000.0)
+    } else if amount >= 10_000.0 {
+        format!("{:.1}万円", amount / 10_000.0)
+    } else {
+        format!("{:.0}円", amount)
+    }
+}
+
+// 毎月の積立額の説明（例: "毎月5.0万円" / "毎月5.0万円から毎年3.0%ずつ増額"）
+pub fn contribution_label(plan: &Plan) -> String {
+    let start = format_yen(plan.current_monthly);
+    match &plan.contribution {
+        ContributionSchedule::Flat => format!("毎月{}", start),
+        ContributionSchedule::PercentStep(rate) => format!("毎月{}から毎年{:.1}%ずつ増額", start, rate * 100.0),
+        ContributionSchedule::AmountStep(amount) => format!("毎月{}から毎年{}ずつ増額", start, format_yen(*amount)),
+        ContributionSchedule::AgeTiers(tiers) => {
+            let tiers: Vec<String> = tiers
+                .iter()
+                .map(|tier| format!("{}歳から{}", tier.age, format_yen(tier.monthly)))
+                .collect();
+            format!("毎月{} ({})", start, tiers.join(" → "))
+        }
+        ContributionSchedule::Yearly(amounts) => format!("毎月{}から年ごとに指定 ({}年分)", start, amounts.len()),
+    }
+}
+
+// 名目の金額に、インフレを考慮する場合は今の価値（実質）での金額を添える
+pub fn format_yen_real(plan: &Plan, amount: f64, months: usize) -> String {
+    match plan.inflation {
+        Some(_) => format!("{} (実質{})", format_yen(amount), format_yen(plan.to_real(amount, months))),
+        None => format_yen(amount),
+    }
+}
+
+// インフレを考慮する場合に表の右に加える、今の価値（実質）の列の見出し（考慮しない場合は何も加えない）
+fn real_header(plan: &Plan, label: &str, width: usize) -> String {
+    match plan.inflation {
+        Some(_) => format!(" {:>width$}", label),
+        None => String::new(),
+    }
+}
+
+// real_header の列に表示する、months ヶ月後の名目の金額を今の価値に換算した金額
+fn real_cell(plan: &Plan, amount: f64, months: usize, width: usize) -> String {
+    match plan.inflation {
+        Some(_) => format!(" {:>width$}", format_yen(plan.to_real(amount, months))),
+        None => String::new(),
+    }
+}
+
+// 元本に対する運用益の割合（元本が1円未満のときは割合に意味がないので "-"）
+pub fn profit_rate_label(profit: f64, principal: f64) -> String {
+    if principal < 1.0 {
+        return "-".to_string();
+    }
+    format!("{:.0}%", profit / principal * 100.0)
+}
+
+// 年次資産推移をNISA口座と課税口座に分けて表示
+pub fn print_nisa_section(plan: &Plan, config: &NisaConfig) {
+    let longest_years = plan.longest_years();
+    let result = nisa::simulate_with_nisa(
+        plan.initial_balance,
+        &plan.contributions(longest_years),
+        plan.simulation_rate(),
+        plan.contribution_timing,
+        config,
+        &plan.tax,
+    );
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🏦 年次資産推移（NISA口座 / 課税口座の内訳）");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("つみたて投資枠 年{} / 成長投資枠 年{}{} / 生涯投資枠 {} (使用済み {})\n",
+             format_yen(config.tsumitate_annual_limit),
+             format_yen(config.growth_annual_limit),
+             if config.use_growth_frame { "" } else { " (使わない)" },
+             format_yen(config.lifetime_limit),
+             format_yen(config.used_lifetime));
+
+    println!("{:<8} {:>14} {:>14} {:>14} {:>14} {:>14}",
+             "経過年数", "資産合計", "NISA(非課税)", "課税口座", "NISA簿価", "課税口座含み益");
+    println!("{}", "─".repeat(90));
+
+    for (year, breakdown) in result.yearly.iter().enumerate() {
+        let year_num = year + 1;
+
+        // 年次推移と同じく5年ごと、または目標到達時、または最終年に表示
+        let total = breakdown.total();
+        let reached = total >= plan.target_at_year(year_num);
+        if year_num % 5 == 0 || reached || year_num == longest_years {
+            let marker = if reached { "🎯" } else { "  " };
+            println!("{}{:>6}年 {:>14} {:>14} {:>14} {:>14} {:>14}",
+                marker,
+                year_num,
+                format_yen_real(plan, total, year_num * 12),
+                format_yen(breakdown.nisa_value),
+                format_yen(breakdown.taxable.value()),
+                format_yen(breakdown.nisa_cost_basis),
+                format_yen(breakdown.taxable.unrealized_gain())
+            );
+        }
+    }
+
+    match result.lifetime_filled_month {
+        Some(month) => println!("\n📌 生涯投資枠は{}で使い切り、以降の積立は課税口座へ", format_months(month)),
+        None => println!("\n📌 {}年間では生涯投資枠を使い切りません（年間投資枠を超えた分は課税口座へ）", longest_years),
+    }
+
+    if let Some(last) = result.yearly.last() {
+        println!("📌 {}年後にすべて売却した場合の手取り: {} (課税口座の税額 {})",
+                 longest_years,
+                 format_yen_real(plan, last.after_tax_total(), longest_years * 12),
+                 format_yen(last.taxable.tax_if_liquidated()));
+    }
+}
+
+// ボーナス月・臨時の積立・休止期間がある月の積立額と資産額（月ごとの積立の記録）を表示
+pub fn print_ledger_section(plan: &Plan) {
+    let longest_years = plan.longest_years();
+    let scheduled = plan.scheduled_contributions(plan.base_monthly(), longest_years);
+    let contributions = plan.contributions(longest_years);
+    let wealth = simulate_monthly_wealth(plan.initial_balance, &contributions, plan.simulation_rate(), plan.contribution_timing);
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("📒 月ごとの積立の記録（ボーナス・臨時の積立・休止のある月）");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("{:<12} {:>12} {:>12} {:>12} {:>14} {:>14}  内容",
+             "年月", "通常の積立", "調整", "積立額", "元本(累計)", "資産額");
+    println!("{}", "─".repeat(100));
+
+    let mut cumulative = plan.initial_balance;
+    for (index, &contribution) in contributions.iter().enumerate() {
+        let month = index + 1;
+        cumulative += contribution;
+
+        // 休止中のボーナス月は積み立てないため、休止のみを表示する
+        let applied: Vec<&Adjustment> = plan
+            .adjustments
+            .iter()
+            .filter(|adjustment| adjustment.applies_to(month))
+            .collect();
+        let paused = applied.iter().any(|adjustment| matches!(adjustment, Adjustment::Pause { .. }));
+        let labels: Vec<&str> = applied
+            .iter()
+            .filter(|adjustment| !(paused && matches!(adjustment, Adjustment::Bonus { .. })))
+            .map(|adjustment| adjustment.label())
+            .collect();
+        if labels.is_empty() {
+            continue;
+        }
+
+        println!("{:<12} {:>12} {:>12} {:>12} {:>14} {:>14}  {}",
+            format!("{}年目 {:>2}月", (month - 1) / 12 + 1, (month - 1) % 12 + 1),
+            format_yen(scheduled[index]),
+            format_yen_signed(contribution - scheduled[index]),
+            format_yen(contribution),
+            format_yen(cumulative),
+            format_yen_real(plan, wealth[index], month),
+            labels.join("・")
+        );
+    }
+}
+
+// 符号付きの金額（増減の表示用）
+fn format_yen_signed(amount: f64) -> String {
+    if amount < 0.0 {
+        format!("-{}", format_yen(-amount))
+    } else {
+        format!("+{}", format_yen(amount))
+    }
+}
+
+// 手数料を差し引いた最終資産と、期間ごとに支払う手数料の総額を表示
+pub fn print_fee_section(plan: &Plan, config: &FundFees) {
+    let longest_years = plan.longest_years();
+    let contributions = plan.contributions(longest_years);
+    let gross = simulate_index_investment(plan.initial_balance, &contributions, plan.simulation_rate(), plan.contribution_timing);
+    let net = fees::simulate_with_fees(plan.initial_balance, &contributions, plan.simulation_rate(), plan.contribution_timing, config);
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("💸 手数料のコスト（期末にすべて売却した場合）");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("信託報酬 年{:.3}% / 購入時手数料 {:.2}% / 信託財産留保額 {:.2}%\n",
+             config.expense_ratio * 100.0,
+             config.front_load * 100.0,
+             config.retention_fee * 100.0);
+
+    println!("{:<8} {:>12} {:>14} {:>12} {:>12} {:>12} {:>12} {:>12}",
+             "期間", "手数料なし", "手数料控除後", "購入時手数料", "信託報酬", "留保額", "手数料合計", "資産の減少");
+    println!("{}", "─".repeat(110));
+
+    for &years in &plan.periods {
+        let year = &net[years - 1];
+        let proceeds = year.proceeds(config);
+
+        println!("{:>6}年 {:>12} {:>14} {:>12} {:>12} {:>12} {:>12} {:>12}",
+            years,
+            format_yen(gross[years - 1]),
+            format_yen_real(plan, proceeds, years * 12),
+            format_yen(year.front_load_paid),
+            format_yen(year.expense_paid),
+            format_yen(year.retention_fee(config)),
+            format_yen(year.total_fees(config)),
+            format_yen(gross[years - 1] - proceeds)
+        );
+    }
+
+    println!("\n※ 資産の減少には、手数料として支払った分が運用されなかったことによる機会損失も含みます");
+}
+
+// 同じ積立計画を各ファンドで運用した場合の最終資産と手数料の総額を、期間ごとに順位付けして表示
+pub fn print_fund_comparison_section(plan: &Plan) {
+    let contributions = plan.contributions(plan.longest_years());
+    let results: Vec<_> = plan
+        .funds
+        .iter()
+        .map(|fund| fees::simulate_with_fees(plan.initial_balance, &contributions, plan.simulation_rate(), plan.contribution_timing, &fund.fees))
+        .collect();
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🏆 ファンド比較（期末にすべて売却した場合）");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+    for &years in &plan.periods {
+        let total_invested = plan.principal(years);
+        let mut ranking: Vec<_> = plan
+            .funds
+            .iter()
+            .zip(&results)
+            .map(|(fund, yearly)| {
+                let year = &yearly[years - 1];
+                (fund, year.proceeds(&fund.fees), year.total_fees(&fund.fees))
+            })
+            .collect();
+        // 最終資産の多い順、同額なら手数料の少ない順
+        ranking.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.2.total_cmp(&b.2)));
+        let best = ranking[0].1;
+
+        println!("\n【{}年後】投資額(累計) {}\n", years, format_yen(total_invested));
+        println!("{:<4} {:<24} {:>10} {:>14} {:>12} {:>12} {:>12}",
+                 "順位", "ファンド", "信託報酬", "最終資産", "運用益", "手数料合計", "1位との差");
+        println!("{}", "─".repeat(100));
+
+        for (rank, (fund, proceeds, total_fees)) in ranking.iter().enumerate() {
+            println!("{:>4} {:<24} {:>9.3}% {:>14} {:>12} {:>12} {:>12}",
+                rank + 1,
+                fund.name,
+                fund.fees.expense_ratio * 100.0,
+                format_yen_real(plan, *proceeds, years * 12),
+                format_yen(proceeds - total_invested),
+                format_yen(*total_fees),
+                format_yen(best - proceeds)
+            );
+        }
+    }
+}
+
+// 期間ごとの税引き後の最終資産（課税口座のみ / 新NISA活用）を表示
+pub fn print_after_tax_section(plan: &Plan) {
+    let longest_years = plan.longest_years();
+    let nisa_config = plan.nisa.clone().unwrap_or_default();
+    let contributions = plan.contributions(longest_years);
+    let taxable_only = taxable::simulate_taxable(plan.initial_balance, &contributions, plan.simulation_rate(), plan.contribution_timing, &plan.tax);
+    let with_nisa = nisa::simulate_with_nisa(
+        plan.initial_balance,
+        &contributions,
+        plan.simulation_rate(),
+        plan.contribution_timing,
+        &nisa_config,
+        &plan.tax,
+    );
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("💴 税引き後の最終資産（期末にすべて売却した場合）");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("譲渡益の税率 {:.3}% (特定口座)\n", plan.tax.capital_gains_rate * 100.0);
+
+    println!("{:<8} {:>12} {:>12} {:>12} {:>14} {:>14} {:>12}",
+             "期間", "税引前", "譲渡益", "税額", "課税口座のみ", "新NISA活用", "NISAの効果");
+    println!("{}", "─".repeat(100));
+
+    for &years in &plan.periods {
+        let sale = taxable_only[years - 1].clone().liquidate();
+        let nisa_after_tax = with_nisa.yearly[years - 1].after_tax_total();
+
+        println!("{:>6}年 {:>12} {:>12} {:>12} {:>14} {:>14} {:>12}",
+            years,
+            format_yen(sale.proceeds),
+            format_yen(sale.gain()),
+            format_yen(sale.tax),
+            format_yen_real(plan, sale.net(), years * 12),
+            format_yen_real(plan, nisa_after_tax, years * 12),
+            format_yen(nisa_after_tax - sale.net())
+        );
+    }
+}
+
+// iDeCoの年次推移（掛金以外の積立との合計）と、一時金受取時の税引き後の金額を表示
+pub fn print_ideco_section(plan: &Plan, config: &IdecoConfig, current_age: u32) {
+    let result = ideco::simulate_ideco(plan.current_monthly, plan.simulation_rate(), plan.contribution_timing, current_age, config);
+    let total_years = result.yearly.len();
+    // iDeCoの掛金を差し引くのは拠出している間だけ（拠出終了後は全額をその他の積立に回す）
+    let contribution_months = result.contribution_years * 12;
+    let other_contributions: Vec<f64> = plan
+        .contributions(total_years)
+        .iter()
+        .enumerate()
+        .map(|(month, &contribution)| {
+            if month < contribution_months {
+                (contribution - result.monthly_contribution).max(0.0)
+            } else {
+                contribution
+            }
+        })
+        .collect();
+    let other_monthly = other_contributions.first().copied().unwrap_or(0.0);
+    let other_wealth = simulate_index_investment(plan.initial_balance, &other_contributions, plan.simulation_rate(), plan.contribution_timing);
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🧓 iDeCo（{}、掛金 月{}）", config.category.label(), format_yen(result.monthly_contribution));
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+    println!("\n現在{}歳 → {}歳まで拠出、{}歳で一時金受取 / 課税所得 {} / 掛金以外の積立 月{}\n",
+             current_age,
+             config.contribution_end_age,
+             config.payout_age,
+             format_yen(config.taxable_income),
+             format_yen(other_monthly));
+
+    println!("{:<8} {:>6} {:>14} {:>14} {:>14} {:>12} {:>12}",
+             "経過年数", "年齢", "iDeCo残高", "その他の積立", "資産合計", "年間節税額", "累計節税額");
+    println!("{}", "─".repeat(95));
+
+    let mut cumulative_saving = 0.0;
+    for (year, entry) in result.yearly.iter().enumerate() {
+        let year_num = year + 1;
+        cumulative_saving += entry.tax_saving;
+
+        // 5年ごと、拠出終了時、受取時に表示
+        if year_num % 5 == 0 || year_num == result.contribution_years || year_num == total_years {
+            println!("{:>6}年 {:>4}歳 {:>14} {:>14} {:>14} {:>12} {:>12}",
+                year_num,
+                entry.age,
+                format_yen(entry.balance),
+                format_yen(other_wealth[year]),
+                format_yen_real(plan, entry.balance + other_wealth[year], year_num * 12),
+                format_yen(entry.tax_saving),
+                format_yen(cumulative_saving)
+            );
+        }
+    }
+
+    let deduction = ideco::retirement_income_deduction(result.contribution_years);
+    println!("\n📌 {}歳で一時金受取: {}", config.payout_age, format_yen(result.payout));
+    println!("   退職所得控除 {} (加入{}年) / 税額 {} / 手取り {}",
+             format_yen(deduction),
+             result.contribution_years,
+             format_yen(result.payout_tax),
+             format_yen_real(plan, result.payout - result.payout_tax, total_years * 12));
+    println!("📌 掛金の所得控除による節税額の合計: {}", format_yen(result.total_tax_saving()));
+}
+
+// モンテカルロの年次パーセンタイルを確定シミュレーションと並べて表示
+pub fn print_monte_carlo_section(plan: &Plan, config: &MonteCarloConfig) {
+    let longest_years = plan.longest_years();
+    let mean = config.mean.unwrap_or(plan.annual_rate);
+    let contributions = plan.contributions(longest_years);
+    let result = monte_carlo::simulate_monte_carlo(
+        plan.initial_balance,
+        &contributions,
+        plan.simulation_rate(),
+        plan.contribution_timing,
+        &plan.monthly_targets(longest_years),
+        &plan.simulation_monte_carlo(config),
+    );
+    let deterministic = simulate_index_investment(plan.initial_balance, &contributions, plan.simulation_rate(), plan.contribution_timing);
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🎲 モンテカルロシミュレーション（{}で{}年間投資した場合）",
+             contribution_label(plan),
+             longest_years);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("期待年利 {:.1}% / ボラティリティ {:.1}% / {} / {}回試行 (シード: {})\n",
+             mean * 100.0,
+             config.volatility * 100.0,
+             config.distribution.label(),
+             config.paths,
+             result.seed);
+
+    println!("{:<8} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+             "経過年数", "確定", "P5", "P25", "P50", "P75", "P95");
+    println!("{}", "─".repeat(95));
+
+    for (year, percentiles) in result.yearly_percentiles().iter().enumerate() {
+        let year_num = year + 1;
+
+        // 年次推移と同じく5年ごと、または最終年に表示
+        if year_num % 5 == 0 || year_num == longest_years {
+            println!("{:>6}年 {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                year_num,
+                format_yen(deterministic[year]),
+                format_yen(percentiles[0]),
+                format_yen(percentiles[1]),
+                format_yen(percentiles[2]),
+                format_yen(percentiles[3]),
+                format_yen(percentiles[4])
+            );
+            // インフレを考慮する場合は、すべての列を今の価値に換算した行を続ける
+            if plan.inflation.is_some() {
+                let real = |amount: f64| format_yen(plan.to_real(amount, year_num * 12));
+                println!("{:>8} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                    "(実質)",
+                    real(deterministic[year]),
+                    real(percentiles[0]),
+                    real(percentiles[1]),
+                    real(percentiles[2]),
+                    real(percentiles[3]),
+                    real(percentiles[4])
+                );
+            }
+        }
+    }
+
+    print_target_probability(plan, &result);
+    print_required_monthly_with_confidence(plan, config, result.seed);
+}
+
+// 指定した確率で目標に到達するために必要な毎月の投資額を表示
+fn print_required_monthly_with_confidence(plan: &Plan, config: &MonteCarloConfig, seed: u64) {
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🛡️ 確率{:.0}%で目標{}に到達するための毎月の投資額",
+             config.confidence * 100.0,
+             target_label(plan));
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("{:<8} {:>14} {:>14} {:>14} {:<12}",
+             "期間", "年利固定", "必要月額", "上乗せ額", "達成可否");
+    println!("{}", "─".repeat(75));
+
+    for &years in &plan.periods {
+        let deterministic = plan.required_monthly(plan.target_at_year(years), years);
+        let fixed = plan.contributions_with_start(0.0, years);
+        let contributions: Vec<(f64, f64)> = plan
+            .contributions_with_start(1.0, years)
+            .iter()
+            .zip(&fixed)
+            .map(|(one, &zero)| (one - zero, zero))
+            .collect();
+        let required = monte_carlo::required_monthly_for_confidence(
+            plan.target_at_year(years),
+            plan.initial_balance,
+            &contributions,
+            plan.simulation_rate(),
+            plan.contribution_timing,
+            seed,
+            &plan.simulation_monte_carlo(config),
+        );
+        let (Some(deterministic), Some(required)) = (deterministic, required) else {
+            println!("{:>6}年 {:>14} {:>14} {:>14} ❌ 積立期間がないため達成不可", years, "-", "-", "-");
+            continue;
+        };
+
+        let achievable = if required <= plan.base_monthly() {
+            "✅ 達成可能"
+        } else {
+            "❌ 足りないよ！"
+        };
+
+        println!("{:>6}年 {:>14} {:>14} {:>14} {}",
+            years,
+            format_yen(deterministic),
+            format_yen(required),
+            format_yen(required - deterministic),
+            achievable
+        );
+    }
+
+    println!("\n※ 期末の資産が目標額以上となる経路の割合が{:.0}%以上になる最小の月額です",
+             config.confidence * 100.0);
+}
+
+// 期間ごとの目標到達確率と、初めて目標に到達する時期の分布を表示
+fn print_target_probability(plan: &Plan, result: &MonteCarloResult) {
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🎯 目標{}の到達確率", target_label(plan));
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("{:<8} {:>14} {:>16} {:>18}",
+             "期間", "期間内に到達", "期末で目標以上", "確定シミュレーション");
+    println!("{}", "─".repeat(75));
+
+    for &years in &plan.periods {
+        let final_wealth = *simulate_index_investment(plan.initial_balance, &plan.contributions(years), plan.simulation_rate(), plan.contribution_timing)
+            .last()
+            .unwrap_or(&0.0);
+        let deterministic = if final_wealth >= plan.target_at_year(years) {
+            "✅ 達成可能"
+        } else {
+            "❌ 足りないよ！"
+        };
+
+        println!("{:>6}年 {:>13.1}% {:>15.1}% {:>18}",
+            years,
+            result.probability_reached_by(years * 12) * 100.0,
+            result.probability_above_at_year(years, plan.target_at_year(years)) * 100.0,
+            deterministic
+        );
+    }
+
+    println!("\n📅 初めて目標に到達する時期（{}年以内に到達した経路の分布）", plan.longest_years());
+    match result.crossing_month_percentiles() {
+        None => println!("  {}年以内に目標へ到達した経路はありませんでした", plan.longest_years()),
+        Some(months) => {
+            let labels = ["P5", "P25", "P50", "P75", "P95"];
+            for (label, month) in labels.iter().zip(months) {
+                println!("  {:<4} {}", label, format_months(month.round() as usize));
+            }
+
+            println!();
+            let histogram = result.crossing_year_histogram();
+            let max_count = *histogram.iter().max().unwrap_or(&0);
+            for (year, &count) in histogram.iter().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+                let share = count as f64 / result.paths() as f64;
+                let bar = "█".repeat((count * 40).div_ceil(max_count.max(1)));
+                println!("  {:>3}年目 {:>5.1}% {}", year + 1, share * 100.0, bar);
+            }
+
+            let never = result.paths() - histogram.iter().sum::<usize>();
+            println!("  未到達 {:>5.1}%", never as f64 / result.paths() as f64 * 100.0);
+        }
+    }
+}
+
+// 積立期間の後、毎月一定額を取り崩した場合の残高の推移と、資産が尽きる時期を表示
+pub fn print_withdrawal_section(plan: &Plan, config: &WithdrawalConfig) {
+    let accumulation_years = plan.longest_years();
+    let starting_balance = retirement_starting_balance(plan);
+    let annual_rate = config.annual_rate.unwrap_or(plan.annual_rate);
+    let result = withdrawal::simulate_fixed_withdrawals(
+        starting_balance,
+        config.monthly_withdrawal,
+        plan.compounded_rate(annual_rate),
+        config.years,
+        plan.contribution_timing,
+    );
+    let retirement_age = plan.age.map(|age| age + accumulation_years as u32);
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🏖️ 取り崩し期間（{}年間の積立後、毎月{}を引き出した場合）",
+             accumulation_years,
+             format_yen(config.monthly_withdrawal));
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("取り崩し開始時の資産 {} / 想定年利 {:.1}% / 取り崩し期間 {}年\n",
+             format_yen_real(plan, starting_balance, accumulation_years * 12),
+             annual_rate * 100.0,
+             config.years);
+
+    println!("{:<8} {:>6} {:>16} {:>16}",
+             "経過年数", "年齢", "引き出し(累計)", "残高");
+    println!("{}", "─".repeat(60));
+
+    let depleted_year = result.depleted_month.map(|month| (month - 1) / 12 + 1);
+    for (year, &balance) in result.yearly_balance.iter().enumerate() {
+        let year_num = year + 1;
+
+        // 5年ごと、資産が尽きた年、または最終年に表示
+        let depleted = depleted_year == Some(year_num);
+        if year_num % 5 == 0 || depleted || year_num == config.years {
+            let marker = if depleted { "⚠️" } else { "  " };
+            println!("{}{:>6}年 {:>6} {:>16} {:>16}",
+                marker,
+                year_num,
+                retirement_age.map_or("-".to_string(), |age| format!("{}歳", age + year_num as u32)),
+                format_yen(result.yearly_withdrawn[year]),
+                format_yen_real(plan, balance, (accumulation_years + year_num) * 12)
+            );
+        }
+        if depleted_year.is_some_and(|depleted| year_num >= depleted) {
+            break;
+        }
+    }
+
+    match result.depleted_month {
+        Some(month) => {
+            let age = retirement_age
+                .map(|age| format!("（{}歳）", age + (month / 12) as u32))
+                .unwrap_or_default();
+            println!("\n📌 取り崩し開始から{}{}で資産が尽きます", format_months(month), age);
+        }
+        None => println!("\n📌 {}年間取り崩しても資産は尽きません（残高 {}）",
+                         config.years,
+                         format_yen(*result.yearly_balance.last().unwrap_or(&0.0))),
+    }
+}
+
+// 取り崩し開始時の資産（現在の積立計画で最長期間まで年利固定で積み立てた額）
+fn retirement_starting_balance(plan: &Plan) -> f64 {
+    *simulate_index_investment(plan.initial_balance, &plan.contributions(plan.longest_years()), plan.simulation_rate(), plan.contribution_timing)
+        .last()
+        .unwrap_or(&plan.initial_balance)
+}
+
+// 取り崩し期間の各年のインフレ率（インフレを考慮しない場合は0%）
+fn retirement_inflation(plan: &Plan, config: &WithdrawalConfig) -> Vec<f64> {
+    let accumulation_years = plan.longest_years();
+    (0..config.years)
+        .map(|year| {
+            plan.inflation
+                .as_ref()
+                .map_or(0.0, |inflation| inflation.rate_for_year(accumulation_years + year))
+        })
+        .collect()
+}
+
+// 取り崩し期間の確率的な月次リターンの経路（平均は取り崩し期間の想定年利。
+// 積立期間の期待年利 mean は使わず、ボラティリティ・分布・試行回数・シードだけをモンテカルロの設定から使う）
+// 経路はすべてを保持せず、使うたびに同じシードから1本ずつ生成し直す（どの方法でも同じ経路になる）
+struct RetirementPaths {
+    config: MonteCarloConfig,
+    seed: u64,
+    mean: f64,
+    months: usize,
+}
+
+impl RetirementPaths {
+    fn new(plan: &Plan, annual_rate: f64, months: usize) -> Self {
+        let config = plan.monte_carlo.clone().unwrap_or_default();
+        let seed = config.seed.unwrap_or_else(rand::random);
+        RetirementPaths {
+            config,
+            seed,
+            mean: plan.compounded_rate(annual_rate),
+            months,
+        }
+    }
+
+    // 経路ごとに月次リターンを生成して f に渡し、その結果を集める
+    fn map<T>(&self, mut f: impl FnMut(&[f64]) -> T) -> Vec<T> {
+        let mut generator = ReturnGenerator::new(self.mean, self.config.volatility, self.config.distribution, self.seed);
+        let mut returns = vec![0.0; self.months];
+        (0..self.config.paths)
+            .map(|_| {
+                returns.iter_mut().for_each(|value| *value = generator.next_return());
+                f(&returns)
+            })
+            .collect()
+    }
+}
+
+// 取り崩しの方法ごとに、年利固定と確率的なリターンでの結果を比較して表示
+pub fn print_withdrawal_strategy_section(plan: &Plan, config: &WithdrawalConfig) {
+    let starting_balance = retirement_starting_balance(plan);
+    let annual_rate = config.annual_rate.unwrap_or(plan.annual_rate);
+    let months = config.years * 12;
+    let params = StrategyParams {
+        monthly_withdrawal: config.monthly_withdrawal,
+        initial_rate: config.initial_rate,
+        // VPW は年単位で計算するので実効年利を使う
+        expected_return: plan.rate_convention.effective_rate(annual_rate),
+    };
+    let inflation = retirement_inflation(plan, config);
+
+    // 定額を基準に、指定された方法を並べる
+    let mut strategies = vec![WithdrawalStrategy::Fixed];
+    strategies.extend(config.strategies.iter().filter(|&&s| s != WithdrawalStrategy::Fixed));
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("⚖️ 取り崩し方法の比較（開始時の資産 {}、{}年間）", format_yen(starting_balance), config.years);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("定額 月{} / 引き出し率 {:.1}% / 想定年利 {:.1}%{}\n",
+             format_yen(config.monthly_withdrawal),
+             config.initial_rate * 100.0,
+             annual_rate * 100.0,
+             plan.inflation.as_ref().map_or(String::new(), |inflation| format!(" / インフレ率 {}", inflation.describe())));
+
+    // 最終残高は取り崩し期間の終わりの物価水準で今の価値に換算する
+    let final_month = (plan.longest_years() + config.years) * 12;
+
+    println!("【年利固定】");
+    println!("{:<24} {:>12} {:>14} {:>12} {:>14} {:>14}{}",
+             "方法", "初年度(年額)", "引き出し総額", "最少の年額", "最終残高", "資産が尽きる",
+             real_header(plan, "最終残高(実質)", 14));
+    println!("{}", "─".repeat(if plan.inflation.is_some() { 115 } else { 100 }));
+
+    let fixed_returns = vec![plan.rate_convention.monthly_rate(annual_rate); months];
+    for &strategy in &strategies {
+        let result = withdrawal::simulate_strategy(starting_balance, strategy, &params, &fixed_returns, &inflation, plan.contribution_timing);
+        println!("{:<24} {:>12} {:>14} {:>12} {:>14} {:>14}{}",
+            strategy.label(),
+            format_yen(result.yearly_withdrawal[0]),
+            format_yen(result.total_withdrawn()),
+            format_yen(result.lowest_withdrawal()),
+            format_yen(result.final_balance()),
+            result.depleted_month.map_or("尽きない".to_string(), format_months),
+            real_cell(plan, result.final_balance(), final_month, 14)
+        );
+    }
+
+    // 確率的なリターン: すべての方法で同じ経路を使う
+    let paths = RetirementPaths::new(plan, annual_rate, months);
+
+    println!("\n【確率的なリターン】ボラティリティ {:.1}% / {} / {}回試行 (シード: {})",
+             paths.config.volatility * 100.0,
+             paths.config.distribution.label(),
+             paths.config.paths,
+             paths.seed);
+    println!("{:<24} {:>10} {:>14} {:>14} {:>14}{}",
+             "方法", "成功率", "引き出し総額P50", "最少の年額P5", "最終残高P50",
+             real_header(plan, "最終残高P50(実質)", 14));
+    println!("{}", "─".repeat(if plan.inflation.is_some() { 105 } else { 90 }));
+
+    for &strategy in &strategies {
+        // 経路ごとの結果は集計に使う値だけを残す
+        let results = paths.map(|returns| {
+            let result = withdrawal::simulate_strategy(starting_balance, strategy, &params, returns, &inflation, plan.contribution_timing);
+            (result.depleted_month.is_none(), result.total_withdrawn(), result.lowest_withdrawal(), result.final_balance())
+        });
+        let success = results.iter().filter(|result| result.0).count();
+        let sorted = |values: Vec<f64>| {
+            let mut values = values;
+            values.sort_by(|a, b| a.total_cmp(b));
+            values
+        };
+        let totals = sorted(results.iter().map(|result| result.1).collect());
+        let lowest = sorted(results.iter().map(|result| result.2).collect());
+        let finals = sorted(results.iter().map(|result| result.3).collect());
+
+        let final_median = monte_carlo::percentile(&finals, 50.0);
+
+        println!("{:<24} {:>9.1}% {:>14} {:>14} {:>14}{}",
+            strategy.label(),
+            success as f64 / results.len() as f64 * 100.0,
+            format_yen(monte_carlo::percentile(&totals, 50.0)),
+            format_yen(monte_carlo::percentile(&lowest, 5.0)),
+            format_yen(final_median),
+            real_cell(plan, final_median, final_month, 14)
+        );
+    }
+
+    println!("\n※ 成功率は取り崩し期間中に資産が尽きなかった経路の割合です。{}",
+             if plan.inflation.is_some() { "(実質) の列以外の金額は名目値です" } else { "金額はすべて名目値です" });
+}
+
+// 取り崩し期間に資産が尽きない最大の引き出し率を、年利固定・モンテカルロ・過去データで求めて表示
+pub fn print_safe_withdrawal_section(plan: &Plan, config: &WithdrawalConfig, success_rate: f64, history: Option<&ReturnSeries>) {
+    let starting_balance = retirement_starting_balance(plan);
+    let annual_rate = config.annual_rate.unwrap_or(plan.annual_rate);
+    let months = config.years * 12;
+    let inflation = retirement_inflation(plan, config);
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🛟 安全な引き出し率（{}年間、成功率{:.0}%）", config.years, success_rate * 100.0);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("開始時の資産 {} / 想定年利 {:.1}%{}\n",
+             format_yen(starting_balance),
+             annual_rate * 100.0,
+             plan.inflation.as_ref().map_or(String::new(), |inflation| format!(" / インフレ率 {}", inflation.describe())));
+
+    // 初年度の月額は取り崩し開始時の物価水準で今の価値に換算する
+    let start_month = plan.longest_years() * 12;
+
+    println!("{:<20} {:>10} {:>14}{}  条件", "リターン", "引き出し率", "初年度の月額", real_header(plan, "(実質)", 14));
+    println!("{}", "─".repeat(if plan.inflation.is_some() { 95 } else { 80 }));
+
+    let print_row = |label: &str, rate: f64, note: String| {
+        println!("{:<20} {:>9.2}% {:>14}{}  {}",
+            label,
+            rate * 100.0,
+            format_yen(starting_balance * rate / 12.0),
+            real_cell(plan, starting_balance * rate / 12.0, start_month, 14),
+            note
+        );
+    };
+
+    let fixed_returns = vec![plan.rate_convention.monthly_rate(annual_rate); months];
+    print_row("年利固定", withdrawal::safe_withdrawal_rate(&fixed_returns, &inflation, plan.contribution_timing), "年利が毎年一定の場合".to_string());
+
+    let paths = RetirementPaths::new(plan, annual_rate, months);
+    let rates = paths.map(|returns| withdrawal::safe_withdrawal_rate(returns, &inflation, plan.contribution_timing));
+    print_row("モンテカルロ",
+              withdrawal::rate_for_success(&rates, success_rate),
+              format!("{}回試行の{:.0}%で尽きない (シード: {})", paths.config.paths, success_rate * 100.0, paths.seed));
+
+    if let Some(series) = history {
+        if series.returns.len() < months {
+            println!("{:<20} データが{}年分に満たないため求められません", "過去データ", config.years);
+        } else {
+            let windows: Vec<(YearMonth, f64)> = series
+                .returns
+                .windows(months)
+                .enumerate()
+                .map(|(offset, window)| (series.first_month.add_months(offset), withdrawal::safe_withdrawal_rate(window, &inflation, plan.contribution_timing)))
+                .collect();
+            let rates: Vec<f64> = windows.iter().map(|&(_, rate)| rate).collect();
+            print_row("過去データ",
+                      withdrawal::rate_for_success(&rates, success_rate),
+                      format!("{}通りの開始月の{:.0}%で尽きない", windows.len(), success_rate * 100.0));
+            if let Some(&(start, rate)) = windows.iter().min_by(|a, b| a.1.total_cmp(&b.1)) {
+                print_row("過去データ(最悪)", rate, format!("{}開始", start));
+            }
+        }
+    }
+
+    println!("\n※ 初年度に開始時の資産のこの割合を引き出し、以降はインフレ率に合わせて増やした場合（4%ルールと同じ）");
+}
+
+// 過去の指数データで、すべての開始月について積立を再現した結果を表示
+pub fn print_historical_section(plan: &Plan, series: &ReturnSeries) {
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("📜 過去データによる再現（{}を積立）", contribution_label(plan));
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("データ期間: {} 〜 {} ({})",
+             series.first_month,
+             series.last_month(),
+             format_months(series.returns.len()));
+
+    // データに収まる最長の期間で再現する
+    let Some(years) = plan
+        .periods
+        .iter()
+        .copied()
+        .filter(|&years| years * 12 <= series.returns.len())
+        .max()
+    else {
+        println!("  データが{}年分に満たないため再現できません", plan.periods[0]);
+        return;
+    };
+
+    let outcomes = historical::rolling_windows(series, plan.initial_balance, &plan.contributions(years), plan.contribution_timing);
+    let total_invested = plan.principal(years);
+
+    println!("{}年間の積立を開始月ごとに再現 ({}通り)\n", years, outcomes.len());
+    println!("{:<9} {:<9} {:>18} {:>18} {:>18}{}",
+             "開始月", "終了月", "最終資産", "総投資額(元本)", "運用益",
+             real_header(plan, "最終資産(実質)", 18));
+    println!("{}", "─".repeat(if plan.inflation.is_some() { 100 } else { 80 }));
+
+    for outcome in &outcomes {
+        let marker = if outcome.final_wealth >= plan.target_at_year(years) { "🎯" } else { "  " };
+        println!("{}{:<9} {:<9} {:>18} {:>18} {:>18}{}",
+            marker,
+            outcome.start.to_string(),
+            outcome.end.to_string(),
+            format_yen(outcome.final_wealth),
+            format_yen(total_invested),
+            format_yen(outcome.final_wealth - total_invested),
+            real_cell(plan, outcome.final_wealth, years * 12, 18)
+        );
+    }
+
+    print_rolling_window_summary(plan, series);
+}
+
+// 期間ごとに、すべての開始月を通した最低・最高・中央値とパーセンタイルを表示
+fn print_rolling_window_summary(plan: &Plan, series: &ReturnSeries) {
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("📊 期間ごとのローリング分析（{}を積立）", contribution_label(plan));
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+    for &years in &plan.periods {
+        let outcomes = historical::rolling_windows(series, plan.initial_balance, &plan.contributions(years), plan.contribution_timing);
+        let Some(summary) = historical::summarize_windows(&outcomes, years) else {
+            println!("\n{:>4}年: データが足りないため分析できません", years);
+            continue;
+        };
+
+        let total_invested = plan.principal(years);
+        let reached = outcomes
+            .iter()
+            .filter(|outcome| outcome.final_wealth >= plan.target_at_year(years))
+            .count();
+
+        println!("\n{:>4}年 ({}通り, 元本{}, 目標到達 {:.1}%)",
+                 summary.years,
+                 summary.count,
+                 format_yen(total_invested),
+                 reached as f64 / summary.count as f64 * 100.0);
+        // インフレを考慮する場合は、今の価値に換算した列があることを見出しで示す
+        if plan.inflation.is_some() {
+            println!("  {:<6} {:>14}{}", "", "最終資産", real_header(plan, "(実質)", 14));
+        }
+        println!("{}", "─".repeat(if plan.inflation.is_some() { 75 } else { 60 }));
+
+        // 表示幅をそろえたラベル（P50 は中央値）
+        let labels = ["P5    ", "P25   ", "中央値", "P75   ", "P95   "];
+        let rows = std::iter::once(("最低  ", &summary.worst))
+            .chain(labels.iter().copied().zip(summary.percentiles.iter()))
+            .chain(std::iter::once(("最高  ", &summary.best)));
+
+        for (label, outcome) in rows {
+            println!("  {} {:>14}{}  ({} 〜 {})",
+                label,
+                format_yen(outcome.final_wealth),
+                real_cell(plan, outcome.final_wealth, years * 12, 14),
+                outcome.start,
+                outcome.end
+            );
+        }
+    }
+}
+
+// 見出し用の目標額（インフレを考慮する場合は今の価値であることを示す）
+pub fn target_label(plan: &Plan) -> String {
+    match plan.inflation {
+        Some(_) => format!("{}（今の価値）", format_yen(plan.target_amount)),
+        None => format_yen(plan.target_amount),
+    }
+}
+
+// インフレ率と、期間ごとの名目の目標額を表示
+pub fn print_inflation_section(plan: &Plan, inflation: &Inflation) {
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🎈 インフレ率 {} を想定した目標額", inflation.describe());
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+
+    println!("{:<8} {:>12} {:>18} {:>18}",
+             "期間", "物価水準", "目標額(今の価値)", "目標額(名目)");
+    println!("{}", "─".repeat(65));
+
+    for &years in &plan.periods {
+        println!("{:>6}年 {:>11.2}倍 {:>18} {:>18}",
+            years,
+            plan.price_level(years * 12),
+            format_yen(plan.target_amount),
+            format_yen(plan.target_at_year(years))
+        );
+    }
+
+    println!("\n※ 以降の表では名目の金額に加えて、今の価値に換算した実質の金額を (実質…) で示します\n");
+}
+
+// 株式インデックスの長期平均として現実的とみなす年利の上限
+pub const REALISTIC_ANNUAL_RATE: f64 = 0.07;
+
+// 現在の積立計画のまま years 年で目標に到達するために必要な年利の表示
+pub fn required_rate_label(plan: &Plan, years: usize) -> String {
+    let target_amount = plan.target_at_year(years);
+    if plan.principal(years) >= target_amount {
+        return "元本で到達".to_string();
+    }
+    match plan.required_rate(target_amount, years) {
+        Some(rate) => format!("{:.1}%", rate * 100.0),
+        None => "100%超".to_string(),
+    }
+}
+
+// 月数を「X年Yヶ月」の形式に変換
+pub fn format_months(months: usize) -> String {
+    match (months / 12, months % 12) {
+        (0, m) => format!("{}ヶ月", m),
+        (y, 0) => format!("{}年", y),
+        (y, m) => format!("{}年{}ヶ月", y, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_yen_negative_amounts() {
+        assert_eq!(format_yen(-27_401_363.0), "-2740.1万円");
+        assert_eq!(format_yen(-250_000_000.0), "-2.50億円");
+        assert_eq!(format_yen(-1234.0), "-1234円");
+        assert_eq!(format_yen(-0.3), "0円");
+        assert_eq!(format_yen_signed(-50_000.0), "-5.0万円");
+    }
+}
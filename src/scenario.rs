@@ -0,0 +1,541 @@
+// シナリオファイル（TOML / JSON）の読み込み
+//
+// 例 (TOML):
+//   name = "田中家"
+//   target = "1億"
+//   rate = 5.0          # 年利 (%)
+//   rate_basis = "nominal"   # 年利の種類 (nominal: 名目年利 / effective: 実効年利)
+//   compounding = "monthly"  # 名目年利の複利の頻度 (monthly / daily / annual / continuous)
+//   monthly = "5万"
+//   initial = "300万"   # すでに保有している資産
+//   timing = "beginning" # 毎月の積立のタイミング (beginning: 月初 / end: 月末)
+//   years = [10, 20, 30]
+//   age = 35            # 現在の年齢
+//   inflation = 2.0     # インフレ率 (%)。年ごとに [3.0, 2.5, 2.0] のようにも指定できる
+//
+//   [contribution]      # 積立額の推移 (以下のいずれか1つ)
+//   step_rate = 3.0     # 毎年 % ずつ増額
+//   # step_amount = "5000"                          # 毎年一定額ずつ増額
+//   # tiers = [{ age = 40, monthly = "7万" }]       # 年齢別の月額 (age が必要)
+//   # yearly = ["5万", "5万", "6万"]                # 年ごとの月額
+//   bonus_months = [6, 12]                          # 毎年ボーナス月に上乗せする
+//   bonus = "20万"
+//   deposits = [{ at = "3-4", amount = "100万" }]   # 3年目の4月に一度だけ上乗せ
+//   pauses = [{ from = "2-4", to = "3-3" }]         # 積立を止める期間
+//
+//   [monte_carlo]       # 省略時はモンテカルロを実行しない
+//   volatility = 15.0   # 年率ボラティリティ (%)
+//   paths = 10000
+//   seed = 42
+//   confidence = 90.0   # 必要月額を逆算するときの目標到達確率 (%)
+//
+//   [nisa]              # 新NISAの枠を考慮する
+//   used = "300万"      # すでに使っている生涯投資枠
+//   growth_frame = true # 成長投資枠でも積み立てるか
+//
+//   [tax]               # 課税口座の税制
+//   capital_gains_rate = 20.315   # 譲渡益の税率 (%)
+//   method = "average"  # 取得価額の計算方法 (average / fifo)
+//
+//   [ideco]             # iDeCoの節税額と受取額を表示する (age が必要)
+//   category = "employee"
+//   taxable_income = "400万"
+//
+//   [fees]              # ファンドの手数料 (%)
+//   expense_ratio = 0.1 # 信託報酬 (年率)
+//   front_load = 0.0    # 購入時手数料
+//   retention_fee = 0.0 # 信託財産留保額
+//
+//   [[funds]]           # 同じ積立計画で比較するファンド (複数書ける)
+//   name = "eMAXIS Slim 全世界株式"
+//   expense_ratio = 0.05775
+//
+//   [withdrawal]        # 積立期間の後の取り崩し
+//   monthly = "20万"    # 毎月の引き出し額
+//   years = 30          # 取り崩す期間
+//   rate = 3.0          # 取り崩し期間の想定年利 (%)
+//   strategies = ["four-percent", "guardrails", "vpw"]   # 定額と比較する方法
+//   withdrawal_rate = 4.0                               # 引き出し率 (%)
+//   success_rate = 95.0  # 安全な引き出し率を求めるときの成功率 (%)
+//
+//   [historical]        # 過去の指数データで積立を再現する
+//   file = "sp500.csv"  # シナリオファイルからの相対パス
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+
+use crate::cli;
+use crate::compounding::{Compounding, RateBasis};
+use crate::contribution::{Adjustment, AgeTier, ContributionSchedule};
+use crate::error::Error;
+use crate::fees::{Fund, FundFees};
+use crate::ideco::{EmploymentCategory, IdecoConfig};
+use crate::inflation::Inflation;
+use crate::monte_carlo::{MonteCarloConfig, ReturnDistribution};
+use crate::nisa::NisaConfig;
+use crate::plan::Plan;
+use crate::taxable::CostBasisMethod;
+use crate::tvm::PaymentTiming;
+use crate::withdrawal::{WithdrawalConfig, WithdrawalStrategy};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioFile {
+    name: Option<String>,
+    target: Option<Yen>,
+    rate: Option<f64>,
+    monthly: Option<Yen>,
+    initial: Option<Yen>,
+    timing: Option<PaymentTiming>,
+    rate_basis: Option<RateBasis>,
+    compounding: Option<Compounding>,
+    contribution: Option<ContributionSection>,
+    years: Option<Vec<usize>>,
+    age: Option<u32>,
+    inflation: Option<InflationField>,
+    monte_carlo: Option<MonteCarloSection>,
+    historical: Option<HistoricalSection>,
+    nisa: Option<NisaSection>,
+    tax: Option<TaxSection>,
+    ideco: Option<IdecoSection>,
+    fees: Option<FeesSection>,
+    funds: Option<Vec<FundSection>>,
+    withdrawal: Option<WithdrawalSection>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContributionSection {
+    step_rate: Option<f64>,
+    step_amount: Option<Yen>,
+    tiers: Option<Vec<TierSection>>,
+    yearly: Option<Vec<Yen>>,
+    bonus_months: Option<Vec<u32>>,
+    bonus: Option<Yen>,
+    deposits: Option<Vec<DepositSection>>,
+    pauses: Option<Vec<PauseSection>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DepositSection {
+    at: String,
+    amount: Yen,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PauseSection {
+    from: String,
+    to: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TierSection {
+    age: u32,
+    monthly: Yen,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NisaSection {
+    used: Option<Yen>,
+    growth_frame: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaxSection {
+    capital_gains_rate: Option<f64>,
+    method: Option<CostBasisMethod>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IdecoSection {
+    category: Option<EmploymentCategory>,
+    monthly: Option<Yen>,
+    taxable_income: Option<Yen>,
+    contribution_end_age: Option<u32>,
+    payout_age: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FeesSection {
+    expense_ratio: Option<f64>,
+    front_load: Option<f64>,
+    retention_fee: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FundSection {
+    name: String,
+    expense_ratio: Option<f64>,
+    front_load: Option<f64>,
+    retention_fee: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WithdrawalSection {
+    monthly: Option<Yen>,
+    years: Option<usize>,
+    rate: Option<f64>,
+    strategies: Option<Vec<WithdrawalStrategy>>,
+    withdrawal_rate: Option<f64>,
+    success_rate: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HistoricalSection {
+    file: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MonteCarloSection {
+    mean: Option<f64>,
+    volatility: Option<f64>,
+    paths: Option<usize>,
+    seed: Option<u64>,
+    distribution: Option<ReturnDistribution>,
+    confidence: Option<f64>,
+}
+
+// インフレ率 (%): 一定の率、または年ごとの率の配列
+#[derive(Debug, Deserialize)]
+#[serde(untagged, expecting = "インフレ率 (%) の数値、または年ごとの率の配列")]
+enum InflationField {
+    Constant(f64),
+    Schedule(Vec<f64>),
+}
+
+// 金額: 数値、または「5万」「1億」のような文字列
+#[derive(Debug)]
+struct Yen(f64);
+
+impl<'de> Deserialize<'de> for Yen {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct YenVisitor;
+
+        impl Visitor<'_> for YenVisitor {
+            type Value = Yen;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("金額 (数値、または \"5万\" \"1億\" のような文字列)")
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Yen, E> {
+                Ok(Yen(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Yen, E> {
+                Ok(Yen(v as f64))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Yen, E> {
+                Ok(Yen(v as f64))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Yen, E> {
+                cli::parse_amount(v).map(Yen).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(YenVisitor)
+    }
+}
+
+pub fn load(path: &Path) -> Result<Plan, Error> {
+    let scenario_error = |message: String| Error::Scenario {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let text = fs::read_to_string(path)
+        .map_err(|e| scenario_error(format!("シナリオファイルを読み込めません: {}", e)))?;
+
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+
+    let file = match extension.as_deref() {
+        Some("toml") => parse_toml(&text),
+        Some("json") => parse_json(&text),
+        _ => Err("拡張子は .toml または .json にしてください".to_string()),
+    }
+    .map_err(scenario_error)?;
+
+    // 値の妥当性はコマンドラインの値で上書きした後に確認する
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    file.into_plan(base_dir).map_err(scenario_error)
+}
+
+fn parse_toml(text: &str) -> Result<ScenarioFile, String> {
+    let deserializer = toml::Deserializer::parse(text).map_err(|e| e.to_string())?;
+    serde_path_to_error::deserialize(deserializer).map_err(describe_error)
+}
+
+fn parse_json(text: &str) -> Result<ScenarioFile, String> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    serde_path_to_error::deserialize(&mut deserializer).map_err(describe_error)
+}
+
+// どの項目で失敗したかをメッセージの先頭に付ける
+fn describe_error<E: fmt::Display>(error: serde_path_to_error::Error<E>) -> String {
+    let path = error.path().to_string();
+    if path == "." {
+        error.inner().to_string()
+    } else {
+        format!("{}: {}", path, error.inner())
+    }
+}
+
+impl ScenarioFile {
+    // ファイルに書かれていない項目は既定値を使う。相対パスは base_dir から解決する
+    fn into_plan(self, base_dir: &Path) -> Result<Plan, String> {
+        let mut plan = Plan::default();
+
+        if let Some(name) = self.name {
+            plan.name = Some(name);
+        }
+        if let Some(Yen(target)) = self.target {
+            plan.target_amount = target;
+        }
+        if let Some(rate) = self.rate {
+            plan.annual_rate = rate / 100.0;
+        }
+        if let Some(Yen(monthly)) = self.monthly {
+            plan.current_monthly = monthly;
+        }
+        if let Some(Yen(initial)) = self.initial {
+            plan.initial_balance = initial;
+        }
+        if let Some(timing) = self.timing {
+            plan.contribution_timing = timing;
+        }
+        if let Some(basis) = self.rate_basis {
+            plan.rate_convention.basis = basis;
+        }
+        if let Some(compounding) = self.compounding {
+            plan.rate_convention.compounding = compounding;
+        }
+        if let Some(section) = self.contribution {
+            plan.adjustments = section.adjustments()?;
+            plan.contribution = section.into_schedule()?;
+            if let ContributionSchedule::Yearly(amounts) = &plan.contribution
+                && let Some(&first) = amounts.first()
+            {
+                if self.monthly.is_some() {
+                    return Err("contribution.yearly: 年ごとの月額は1年目の月額を兼ねるので、monthly と同時には指定できません".to_string());
+                }
+                plan.current_monthly = first;
+            }
+        }
+        if let Some(mut years) = self.years {
+            years.sort_unstable();
+            years.dedup();
+            plan.periods = years;
+        }
+        if let Some(age) = self.age {
+            plan.age = Some(age);
+        }
+        match self.inflation {
+            Some(InflationField::Constant(rate)) => plan.inflation = Some(Inflation::constant(rate / 100.0)),
+            Some(InflationField::Schedule(rates)) => {
+                plan.inflation = Some(Inflation::schedule(rates.iter().map(|rate| rate / 100.0).collect()))
+            }
+            None => {}
+        }
+        if let Some(section) = self.monte_carlo {
+            let mut config = MonteCarloConfig::default();
+            if let Some(mean) = section.mean {
+                config.mean = Some(mean / 100.0);
+            }
+            if let Some(volatility) = section.volatility {
+                config.volatility = volatility / 100.0;
+            }
+            if let Some(paths) = section.paths {
+                config.paths = paths;
+            }
+            if let Some(seed) = section.seed {
+                config.seed = Some(seed);
+            }
+            if let Some(distribution) = section.distribution {
+                config.distribution = distribution;
+            }
+            if let Some(confidence) = section.confidence {
+                config.confidence = confidence / 100.0;
+            }
+            plan.monte_carlo = Some(config);
+        }
+        if let Some(section) = self.nisa {
+            let mut config = NisaConfig::default();
+            if let Some(Yen(used)) = section.used {
+                config.used_lifetime = used;
+            }
+            if let Some(growth_frame) = section.growth_frame {
+                config.use_growth_frame = growth_frame;
+            }
+            plan.nisa = Some(config);
+        }
+        if let Some(section) = self.tax {
+            if let Some(rate) = section.capital_gains_rate {
+                plan.tax.capital_gains_rate = rate / 100.0;
+            }
+            if let Some(method) = section.method {
+                plan.tax.method = method;
+            }
+        }
+        if let Some(section) = self.ideco {
+            let mut config = IdecoConfig::default();
+            if let Some(category) = section.category {
+                config.category = category;
+            }
+            if let Some(Yen(monthly)) = section.monthly {
+                config.monthly_contribution = Some(monthly);
+            }
+            if let Some(Yen(income)) = section.taxable_income {
+                config.taxable_income = income;
+            }
+            if let Some(age) = section.contribution_end_age {
+                config.contribution_end_age = age;
+            }
+            if let Some(age) = section.payout_age {
+                config.payout_age = age;
+            }
+            plan.ideco = Some(config);
+        }
+        if let Some(section) = self.fees {
+            let mut fees = FundFees::default();
+            if let Some(rate) = section.expense_ratio {
+                fees.expense_ratio = rate / 100.0;
+            }
+            if let Some(rate) = section.front_load {
+                fees.front_load = rate / 100.0;
+            }
+            if let Some(rate) = section.retention_fee {
+                fees.retention_fee = rate / 100.0;
+            }
+            plan.fees = Some(fees);
+        }
+        if let Some(funds) = self.funds {
+            plan.funds = funds
+                .into_iter()
+                .map(|section| Fund {
+                    name: section.name,
+                    fees: FundFees {
+                        expense_ratio: section.expense_ratio.unwrap_or(0.0) / 100.0,
+                        front_load: section.front_load.unwrap_or(0.0) / 100.0,
+                        retention_fee: section.retention_fee.unwrap_or(0.0) / 100.0,
+                    },
+                })
+                .collect();
+        }
+        if let Some(section) = self.withdrawal {
+            let mut config = WithdrawalConfig::default();
+            if let Some(Yen(monthly)) = section.monthly {
+                config.monthly_withdrawal = monthly;
+            }
+            if let Some(years) = section.years {
+                config.years = years;
+            }
+            if let Some(rate) = section.rate {
+                config.annual_rate = Some(rate / 100.0);
+            }
+            if let Some(strategies) = section.strategies {
+                config.strategies = strategies;
+            }
+            if let Some(rate) = section.withdrawal_rate {
+                config.initial_rate = rate / 100.0;
+            }
+            if let Some(rate) = section.success_rate {
+                config.success_rate = Some(rate / 100.0);
+            }
+            plan.withdrawal = Some(config);
+        }
+        if let Some(section) = self.historical {
+            plan.history_file = Some(base_dir.join(section.file));
+        }
+
+        Ok(plan)
+    }
+}
+
+impl ContributionSection {
+    fn adjustments(&self) -> Result<Vec<Adjustment>, String> {
+        let mut adjustments = Vec::new();
+        match (&self.bonus_months, &self.bonus) {
+            (Some(months), Some(Yen(amount))) => adjustments.push(Adjustment::Bonus {
+                months: months.clone(),
+                amount: *amount,
+            }),
+            (None, None) => {}
+            _ => return Err("contribution: bonus_months と bonus は両方指定してください".to_string()),
+        }
+        for deposit in self.deposits.iter().flatten() {
+            adjustments.push(Adjustment::Deposit {
+                month: cli::parse_plan_month(&deposit.at).map_err(|e| format!("contribution.deposits: {}", e))?,
+                amount: deposit.amount.0,
+            });
+        }
+        for pause in self.pauses.iter().flatten() {
+            adjustments.push(Adjustment::Pause {
+                from: cli::parse_plan_month(&pause.from).map_err(|e| format!("contribution.pauses: {}", e))?,
+                to: cli::parse_plan_month(&pause.to).map_err(|e| format!("contribution.pauses: {}", e))?,
+            });
+        }
+        Ok(adjustments)
+    }
+
+    fn into_schedule(self) -> Result<ContributionSchedule, String> {
+        let mut schedules = Vec::new();
+        if let Some(rate) = self.step_rate {
+            schedules.push(ContributionSchedule::PercentStep(rate / 100.0));
+        }
+        if let Some(Yen(amount)) = self.step_amount {
+            schedules.push(ContributionSchedule::AmountStep(amount));
+        }
+        if let Some(tiers) = self.tiers {
+            schedules.push(ContributionSchedule::AgeTiers(
+                tiers
+                    .into_iter()
+                    .map(|tier| AgeTier {
+                        age: tier.age,
+                        monthly: tier.monthly.0,
+                    })
+                    .collect(),
+            ));
+        }
+        if let Some(amounts) = self.yearly {
+            schedules.push(ContributionSchedule::Yearly(amounts.into_iter().map(|Yen(amount)| amount).collect()));
+        }
+
+        match schedules.len() {
+            0 => Ok(ContributionSchedule::Flat),
+            1 => Ok(schedules.remove(0)),
+            _ => Err("contribution: step_rate / step_amount / tiers / yearly はいずれか1つを指定してください".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1つのファイルに monthly と年ごとの月額を両方書いた場合は拒否すること
+    #[test]
+    fn rejects_monthly_with_yearly_schedule() {
+        let file = parse_toml("monthly = \"3万\"\n[contribution]\nyearly = [\"5万\", \"6万\"]\n").unwrap();
+        assert!(file.into_plan(Path::new(".")).unwrap_err().starts_with("contribution.yearly"));
+    }
+}
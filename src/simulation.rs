@@ -0,0 +1,328 @@
+// 年利固定の積立シミュレーションと必要月額の計算
+
+use crate::tvm::{self, PaymentTiming};
+
+// 複利計算: 毎月の積立で目標金額に到達するための月額を計算（積立では届かない場合は None）
+pub fn calculate_monthly_investment_for_target(
+    target_amount: f64,
+    initial_balance: f64,
+    annual_rate: f64,
+    years: usize,
+    timing: PaymentTiming,
+) -> Option<f64> {
+    let months = years * 12;
+    let monthly_rate = annual_rate / 12.0;
+
+    // 積み立てる月がなければ、初期資産だけで届くかどうか
+    if months == 0 {
+        return (initial_balance >= target_amount).then_some(0.0);
+    }
+
+    // 将来価値の年金現在価値の逆算（Excel の PMT）
+    // FV = PV × (1 + r)^n + PMT × (1 + r × type) × [(1 + r)^n - 1] / r
+    // PMT = [FV - PV × (1 + r)^n] × r / {(1 + r × type) × [(1 + r)^n - 1]}
+    // 積立と初期資産は支払い（マイナス）、目標額は受け取り（プラス）とする
+    let payment = -tvm::pmt(monthly_rate, months as f64, -initial_balance, target_amount, timing);
+    // 初期資産だけで目標に届く場合は積立不要
+    Some(payment.max(0.0))
+}
+
+// 積立額のスケジュールを保ったまま、目標到達に必要な1年目の月額を計算
+//
+// contributions_for(start) は1年目の月額を start としたときの各月の積立額。
+// どのスケジュールでも各月の積立額は start について線形（一定額の増額は定数項）なので、
+// start = 0 と 1 の将来価値の差から1円あたりの将来価値を求めて逆算する。
+// 休止期間が全期間にわたるなど、月額を増やしても将来価値が増えず目標に届かない場合は None
+pub fn calculate_starting_monthly_for_target<F>(
+    target_amount: f64,
+    initial_balance: f64,
+    annual_rate: f64,
+    timing: PaymentTiming,
+    contributions_for: F,
+) -> Option<f64>
+where
+    F: Fn(f64) -> Vec<f64>,
+{
+    let fixed = contributions_for(0.0);
+    let unit = contributions_for(1.0);
+
+    let fixed_value = future_value(initial_balance, &fixed, annual_rate, timing);
+    let value_per_yen = future_value(0.0, &unit, annual_rate, timing) - future_value(0.0, &fixed, annual_rate, timing);
+    if value_per_yen <= 0.0 {
+        return (fixed_value >= target_amount).then_some(0.0);
+    }
+    Some(((target_amount - fixed_value) / value_per_yen).max(0.0))
+}
+
+// 1ヶ月分の積立と運用を、積立のタイミングに合わせた順に行う（月初なら 積立 → 運用、月末なら 運用 → 積立）
+// 資産額だけでなく口座ごとの残高や取得価額を持つシミュレーションも、この順序に従う
+pub fn monthly_step<S>(state: &mut S, timing: PaymentTiming, contribute: impl FnOnce(&mut S), grow: impl FnOnce(&mut S)) {
+    match timing {
+        PaymentTiming::Beginning => {
+            contribute(state);
+            grow(state);
+        }
+        PaymentTiming::End => {
+            grow(state);
+            contribute(state);
+        }
+    }
+}
+
+// 1ヶ月分の積立と運用
+pub fn grow_with_contribution(wealth: f64, contribution: f64, monthly_return: f64, timing: PaymentTiming) -> f64 {
+    let mut wealth = wealth;
+    monthly_step(&mut wealth, timing, |wealth| *wealth += contribution, |wealth| *wealth *= 1.0 + monthly_return);
+    wealth
+}
+
+// 初期資産と各月の積立額の将来価値
+fn future_value(initial_balance: f64, contributions: &[f64], annual_rate: f64, timing: PaymentTiming) -> f64 {
+    let monthly_rate = annual_rate / 12.0;
+    contributions
+        .iter()
+        .fold(initial_balance, |value, &amount| grow_with_contribution(value, amount, monthly_rate, timing))
+}
+
+// 目標金額に到達するために必要な年利（RATE）
+//
+// 必要月額の計算と同じ将来価値の式を年利について解く。毎月一定額ならば Excel の RATE（ニュートン法）で求め、
+// 積立額が月ごとに変わる場合や収束しない場合は、将来価値が年利について単調に増えることを使って二分法で求める。
+// -50%〜100%の範囲で解がなければ None
+pub fn calculate_required_annual_rate(
+    target_amount: f64,
+    initial_balance: f64,
+    contributions: &[f64],
+    timing: PaymentTiming,
+) -> Option<f64> {
+    const LOWER: f64 = -0.5;
+    const UPPER: f64 = 1.0;
+    let shortfall = |annual_rate: f64| future_value(initial_balance, contributions, annual_rate, timing) - target_amount;
+
+    if shortfall(UPPER) < 0.0 || shortfall(LOWER) > 0.0 {
+        return None;
+    }
+
+    if let Some(&monthly) = contributions.first()
+        && contributions.iter().all(|&amount| amount == monthly)
+        && let Some(monthly_rate) = tvm::rate(
+            contributions.len() as f64,
+            -monthly,
+            -initial_balance,
+            target_amount,
+            timing,
+            0.05 / 12.0,
+        )
+        && (LOWER..=UPPER).contains(&(monthly_rate * 12.0))
+    {
+        return Some(monthly_rate * 12.0);
+    }
+
+    let (mut low, mut high) = (LOWER, UPPER);
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if shortfall(middle) < 0.0 {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+// 毎月一定額を積み立てて目標金額に到達するまでの月数（NPER）
+//
+// m ヶ月後の資産は
+//   W(m) = PV × (1 + r)^m + PMT × (1 + r × type) × [(1 + r)^m - 1] / r
+// これを m について解き、到達する最初の月に切り上げる。到達しない場合は None
+pub fn calculate_months_to_target(
+    target_amount: f64,
+    initial_balance: f64,
+    monthly_investment: f64,
+    annual_rate: f64,
+    timing: PaymentTiming,
+) -> Option<usize> {
+    if initial_balance >= target_amount {
+        return Some(0);
+    }
+    let monthly_rate = annual_rate / 12.0;
+
+    // Excel の NPER
+    let months = tvm::nper(monthly_rate, -monthly_investment, -initial_balance, target_amount, timing)?;
+
+    // 丸め誤差でちょうど到達する月を超えないように少しだけ差し引く
+    (months >= 0.0).then(|| (months - 1e-9).ceil().max(1.0) as usize)
+}
+
+// 各月の積立額と各月の目標額を与えて、月末の資産が初めて目標額以上になる月（1始まり）
+// 積立額が変わる場合や、インフレで目標額が変わる場合に使う
+pub fn months_to_reach(
+    initial_balance: f64,
+    contributions: &[f64],
+    annual_rate: f64,
+    timing: PaymentTiming,
+    monthly_targets: &[f64],
+) -> Option<usize> {
+    simulate_monthly_wealth(initial_balance, contributions, annual_rate, timing)
+        .iter()
+        .zip(monthly_targets)
+        .position(|(wealth, target)| wealth >= target)
+        .map(|index| index + 1)
+}
+
+// 実際にシミュレーション（年利固定）
+pub fn simulate_index_investment(
+    initial_balance: f64,
+    // 各月の積立額（contributions[0] が1ヶ月目）
+    contributions: &[f64],
+    annual_rate: f64,
+    timing: PaymentTiming,
+) -> Vec<f64> {
+    let monthly_rate = annual_rate / 12.0;
+
+    simulate_with_monthly_returns(
+        initial_balance,
+        contributions,
+        std::iter::repeat_n(monthly_rate, contributions.len()),
+        timing,
+    )
+}
+
+// 月ごとのリターンを与えてシミュレーション（過去データの再現などに使用）
+pub fn simulate_with_monthly_returns<I>(
+    initial_balance: f64,
+    contributions: &[f64],
+    monthly_returns: I,
+    timing: PaymentTiming,
+) -> Vec<f64>
+where
+    I: IntoIterator<Item = f64>,
+{
+    // 初期資産は1ヶ月目の積立と同じく、1ヶ月目のリターンから運用される
+    let mut wealth = initial_balance;
+    let mut yearly_wealth = Vec::new();
+
+    for (index, (contribution, monthly_return)) in contributions.iter().zip(monthly_returns).enumerate() {
+        let month = index + 1;
+
+        // 毎月の積立と月次の利息
+        wealth = grow_with_contribution(wealth, *contribution, monthly_return, timing);
+
+        // 年末の資産を記録
+        if month % 12 == 0 {
+            yearly_wealth.push(wealth);
+        }
+    }
+
+    yearly_wealth
+}
+
+// 毎月末の資産額（月ごとの積立の記録に使用）
+pub fn simulate_monthly_wealth(
+    initial_balance: f64,
+    contributions: &[f64],
+    annual_rate: f64,
+    timing: PaymentTiming,
+) -> Vec<f64> {
+    let monthly_rate = annual_rate / 12.0;
+    let mut wealth = initial_balance;
+
+    contributions
+        .iter()
+        .map(|&contribution| {
+            wealth = grow_with_contribution(wealth, contribution, monthly_rate, timing);
+            wealth
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn final_wealth(initial_balance: f64, contributions: &[f64], annual_rate: f64, timing: PaymentTiming) -> f64 {
+        *simulate_index_investment(initial_balance, contributions, annual_rate, timing).last().unwrap()
+    }
+
+    // 必要月額をシミュレーションに戻すと、ちょうど目標額になること
+    #[test]
+    fn required_monthly_simulates_back_to_target() {
+        let target_amount = 100_000_000.0;
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            for (initial_balance, annual_rate, years) in [(0.0, 0.05, 30), (3_000_000.0, 0.07, 20), (500_000.0, 0.0, 10)] {
+                let monthly = calculate_monthly_investment_for_target(target_amount, initial_balance, annual_rate, years, timing).unwrap();
+                let wealth = final_wealth(initial_balance, &vec![monthly; years * 12], annual_rate, timing);
+                assert!((wealth - target_amount).abs() < 1e-3, "{:?}: {} != {}", timing, wealth, target_amount);
+            }
+        }
+    }
+
+    // 年利0%では単純な積み上げ、マイナスの年利でも目標額まで戻ること
+    #[test]
+    fn zero_and_negative_rates() {
+        let target_amount = 10_000_000.0;
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            let monthly = calculate_monthly_investment_for_target(target_amount, 400_000.0, 0.0, 20, timing).unwrap();
+            assert!((monthly - 40_000.0).abs() < 1e-9, "{:?}: {}", timing, monthly);
+            assert_eq!(calculate_months_to_target(target_amount, 400_000.0, 40_000.0, 0.0, timing), Some(240));
+
+            let monthly = calculate_monthly_investment_for_target(target_amount, 400_000.0, -0.03, 20, timing).unwrap();
+            let wealth = final_wealth(400_000.0, &vec![monthly; 240], -0.03, timing);
+            assert!(monthly > 40_000.0 && (wealth - target_amount).abs() < 1e-3, "{:?}: {}", timing, wealth);
+        }
+        assert_eq!(calculate_monthly_investment_for_target(target_amount, 0.0, 0.05, 0, PaymentTiming::End), None);
+    }
+
+    // 積立額のスケジュールがある場合も、1年目の月額から戻した結果が目標額になること
+    #[test]
+    fn starting_monthly_simulates_back_to_target() {
+        let target_amount = 50_000_000.0;
+        let schedule = |start: f64| -> Vec<f64> {
+            (0..240).map(|month| start * 1.03f64.powi(month / 12) + if month % 12 == 5 { 200_000.0 } else { 0.0 }).collect()
+        };
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            let start = calculate_starting_monthly_for_target(target_amount, 1_000_000.0, 0.05, timing, schedule).unwrap();
+            let wealth = final_wealth(1_000_000.0, &schedule(start), 0.05, timing);
+            assert!((wealth - target_amount).abs() < 1e-3, "{:?}: {} != {}", timing, wealth, target_amount);
+        }
+    }
+
+    // 必要な年利をシミュレーションに戻すと目標額になり、-50%〜100%で解がなければ None になること
+    #[test]
+    fn required_rate_simulates_back_to_target() {
+        let target_amount = 100_000_000.0;
+        let stepped: Vec<f64> = (0..360).map(|month| 50_000.0 * 1.03f64.powi(month / 12)).collect();
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            for contributions in [vec![50_000.0; 360], stepped.clone()] {
+                let rate = calculate_required_annual_rate(target_amount, 1_000_000.0, &contributions, timing).unwrap();
+                let wealth = final_wealth(1_000_000.0, &contributions, rate, timing);
+                assert!((wealth - target_amount).abs() < 1e-2, "{:?}: {} != {}", timing, wealth, target_amount);
+            }
+            // 100%の年利でも届かない
+            assert_eq!(calculate_required_annual_rate(1e15, 0.0, &[10_000.0; 12], timing), None);
+            // -50%の年利でも目標額を超える
+            assert_eq!(calculate_required_annual_rate(1_000_000.0, 1e9, &[10_000.0; 120], timing), None);
+        }
+    }
+
+    // 全期間積み立てない場合は、初期資産だけで届かなければ None
+    #[test]
+    fn starting_monthly_without_contributions() {
+        let no_contributions = |_start: f64| vec![0.0; 60];
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            assert_eq!(calculate_starting_monthly_for_target(10_000_000.0, 1_000_000.0, 0.05, timing, no_contributions), None);
+            assert_eq!(calculate_starting_monthly_for_target(1_000_000.0, 1_000_000.0, 0.05, timing, no_contributions), Some(0.0));
+        }
+    }
+
+    // 目標到達までの月数は、シミュレーションで初めて目標額を超える月と一致すること
+    #[test]
+    fn months_to_target_matches_simulation() {
+        let target_amount = 30_000_000.0;
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            let months = calculate_months_to_target(target_amount, 1_000_000.0, 80_000.0, 0.05, timing).unwrap();
+            let reached = months_to_reach(1_000_000.0, &vec![80_000.0; 1200], 0.05, timing, &vec![target_amount; 1200]);
+            assert_eq!(Some(months), reached, "{:?}", timing);
+        }
+    }
+}
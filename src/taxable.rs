@@ -0,0 +1,266 @@
+// 課税口座（特定口座）
+//
+// 買い付けごとにロット（口数と取得価額）を記録し、売却時に譲渡益へ課税する。
+// 基準価額は1口1円から始めて、月次のリターンで変動させる。
+// 損失の繰越控除や年内の損益通算は考えない（売却ごとに利益が出た分だけ課税）。
+
+use serde::Deserialize;
+
+use crate::error::PlanError;
+use crate::simulation::monthly_step;
+use crate::tvm::PaymentTiming;
+
+// 上場株式等の譲渡益に対する税率（所得税15% + 復興特別所得税0.315% + 住民税5%）
+pub const CAPITAL_GAINS_TAX_RATE: f64 = 0.20315;
+
+// 売却時の取得価額の計算方法
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostBasisMethod {
+    // 総平均法に準ずる方法（特定口座での計算方法）
+    Average,
+    // 古いロットから売却する
+    Fifo,
+}
+
+impl CostBasisMethod {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "average" => Ok(CostBasisMethod::Average),
+            "fifo" => Ok(CostBasisMethod::Fifo),
+            _ => Err(format!("取得価額の計算方法は average または fifo を指定してください: {}", value)),
+        }
+    }
+}
+
+// 課税口座の税制の設定
+#[derive(Debug, Clone)]
+pub struct TaxConfig {
+    pub capital_gains_rate: f64,
+    pub method: CostBasisMethod,
+}
+
+impl Default for TaxConfig {
+    fn default() -> Self {
+        TaxConfig {
+            capital_gains_rate: CAPITAL_GAINS_TAX_RATE,
+            method: CostBasisMethod::Average,
+        }
+    }
+}
+
+impl TaxConfig {
+    pub fn validate(&self) -> Result<(), PlanError> {
+        if !(0.0..1.0).contains(&self.capital_gains_rate) {
+            return Err(PlanError::new("tax.capital_gains_rate", "税率は0%以上100%未満で指定してください"));
+        }
+        Ok(())
+    }
+
+    pub fn new_account(&self) -> TaxableAccount {
+        TaxableAccount::new(self.capital_gains_rate, self.method)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Lot {
+    units: f64,
+    cost: f64,
+}
+
+// 売却の結果
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sale {
+    // 売却額（税引前）
+    pub proceeds: f64,
+    pub cost: f64,
+    pub tax: f64,
+}
+
+impl Sale {
+    pub fn gain(&self) -> f64 {
+        self.proceeds - self.cost
+    }
+
+    // 手取り額
+    pub fn net(&self) -> f64 {
+        self.proceeds - self.tax
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaxableAccount {
+    lots: Vec<Lot>,
+    unit_price: f64,
+    pub tax_rate: f64,
+    pub method: CostBasisMethod,
+    // これまでに納めた税額の合計
+    pub tax_paid: f64,
+}
+
+impl TaxableAccount {
+    pub fn new(tax_rate: f64, method: CostBasisMethod) -> Self {
+        TaxableAccount {
+            lots: Vec::new(),
+            unit_price: 1.0,
+            tax_rate,
+            method,
+            tax_paid: 0.0,
+        }
+    }
+
+    pub fn deposit(&mut self, amount: f64) {
+        if amount <= 0.0 {
+            return;
+        }
+        self.lots.push(Lot {
+            units: amount / self.unit_price,
+            cost: amount,
+        });
+    }
+
+    pub fn grow(&mut self, monthly_return: f64) {
+        self.unit_price *= 1.0 + monthly_return;
+    }
+
+    fn units(&self) -> f64 {
+        self.lots.iter().fold(0.0, |total, lot| total + lot.units)
+    }
+
+    // 時価評価額
+    pub fn value(&self) -> f64 {
+        self.units() * self.unit_price
+    }
+
+    // 取得価額の合計
+    pub fn cost_basis(&self) -> f64 {
+        self.lots.iter().fold(0.0, |total, lot| total + lot.cost)
+    }
+
+    // 含み益（マイナスの場合は含み損）
+    pub fn unrealized_gain(&self) -> f64 {
+        self.value() - self.cost_basis()
+    }
+
+    // 指定した金額（税引前）を売却し、譲渡益に課税する
+    pub fn sell(&mut self, amount: f64) -> Sale {
+        let total_units = self.units();
+        if amount <= 0.0 || total_units <= 0.0 || self.unit_price <= 0.0 {
+            return Sale::default();
+        }
+
+        let units_to_sell = (amount / self.unit_price).min(total_units);
+        let cost = match self.method {
+            CostBasisMethod::Average => {
+                // すべてのロットから口数に比例して取り崩す
+                let fraction = units_to_sell / total_units;
+                let mut cost = 0.0;
+                for lot in &mut self.lots {
+                    cost += lot.cost * fraction;
+                    lot.cost *= 1.0 - fraction;
+                    lot.units *= 1.0 - fraction;
+                }
+                cost
+            }
+            CostBasisMethod::Fifo => {
+                let mut remaining = units_to_sell;
+                let mut cost = 0.0;
+                for lot in &mut self.lots {
+                    if remaining <= 0.0 {
+                        break;
+                    }
+                    let sold = remaining.min(lot.units);
+                    let lot_cost = lot.cost * sold / lot.units;
+                    cost += lot_cost;
+                    lot.cost -= lot_cost;
+                    lot.units -= sold;
+                    remaining -= sold;
+                }
+                cost
+            }
+        };
+        self.lots.retain(|lot| lot.units > 1e-9);
+
+        let proceeds = units_to_sell * self.unit_price;
+        let tax = (proceeds - cost).max(0.0) * self.tax_rate;
+        self.tax_paid += tax;
+        Sale { proceeds, cost, tax }
+    }
+
+    // すべて売却した場合の税額（口座の状態は変えない）
+    pub fn tax_if_liquidated(&self) -> f64 {
+        self.clone().liquidate().tax
+    }
+
+    // すべて売却する
+    pub fn liquidate(&mut self) -> Sale {
+        let value = self.value();
+        self.sell(value)
+    }
+}
+
+// 課税口座だけで積み立てた場合の各年末の口座
+pub fn simulate_taxable(
+    initial_balance: f64,
+    // 各月の積立額（contributions[0] が1ヶ月目）
+    contributions: &[f64],
+    annual_rate: f64,
+    timing: PaymentTiming,
+    tax: &TaxConfig,
+) -> Vec<TaxableAccount> {
+    let monthly_rate = annual_rate / 12.0;
+    let mut account = tax.new_account();
+    let mut yearly = Vec::with_capacity(contributions.len() / 12);
+
+    // 初期資産は今の時価で買い付けたものとみなす（取得価額 = 初期資産）
+    account.deposit(initial_balance);
+
+    for (index, &contribution) in contributions.iter().enumerate() {
+        let month = index + 1;
+        // 月末に積み立てる場合は、その月の運用の後の価格で買い付ける
+        monthly_step(
+            &mut account,
+            timing,
+            |account| account.deposit(contribution),
+            |account| account.grow(monthly_rate),
+        );
+
+        if month % 12 == 0 {
+            yearly.push(account.clone());
+        }
+    }
+
+    yearly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 基準価額1円で10万円、2円で10万円を買い付けた口座から15万円（7.5万口）を売却する
+    fn sell_after_two_lots(method: CostBasisMethod) -> (Sale, TaxableAccount) {
+        let mut account = TaxableAccount::new(CAPITAL_GAINS_TAX_RATE, method);
+        account.deposit(100_000.0);
+        account.grow(1.0);
+        account.deposit(100_000.0);
+        let sale = account.sell(150_000.0);
+        (sale, account)
+    }
+
+    #[test]
+    fn sell_with_each_cost_basis_method() {
+        // 総平均法: 取得単価は 20万円 / 15万口 なので、7.5万口の取得価額は10万円
+        let (sale, account) = sell_after_two_lots(CostBasisMethod::Average);
+        assert!((sale.proceeds - 150_000.0).abs() < 1e-6);
+        assert!((sale.cost - 100_000.0).abs() < 1e-6);
+        assert!((sale.tax - 50_000.0 * CAPITAL_GAINS_TAX_RATE).abs() < 1e-6);
+        assert!((account.cost_basis() - 100_000.0).abs() < 1e-6);
+
+        // 先入先出: 1口1円で買った最初のロットから7.5万口を売るので、取得価額は7.5万円
+        let (sale, account) = sell_after_two_lots(CostBasisMethod::Fifo);
+        assert!((sale.cost - 75_000.0).abs() < 1e-6);
+        assert!((sale.tax - 75_000.0 * CAPITAL_GAINS_TAX_RATE).abs() < 1e-6);
+        assert!((account.cost_basis() - 125_000.0).abs() < 1e-6);
+        assert!((account.value() - 150_000.0).abs() < 1e-6);
+    }
+}
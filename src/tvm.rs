@@ -0,0 +1,221 @@
+// 貨幣の時間価値の関数（Excel の FV / PV / PMT / NPER / RATE / IPMT と同じ仕様）
+//
+// 表計算ソフトと同じ数字になるように、引数の順序・符号・支払時期は Excel に合わせる。
+//   - 受け取るお金をプラス、支払うお金（積立など）をマイナスで表す
+//   - rate は1期あたりの利率（月次なら年利 / 12）、nper は期数
+//   - timing は Excel の「支払期日」（0 = 期末、1 = 期首）
+// いずれも次の式を満たす値を求める。
+//   pv × (1 + rate)^nper + pmt × (1 + rate × type) × [(1 + rate)^nper - 1] / rate + fv = 0
+//
+// 表計算ソフトと同じ関数一式として使えるように、このツールで使っていない関数（FV / PV / IPMT）も含める。
+
+use serde::Deserialize;
+
+// 支払いの時期（Excel の type 引数）。毎月の積立のタイミングにも使う
+// 既定値は持たない（Excel の既定は期末、シミュレーターの既定は Plan::default の月初）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentTiming {
+    // 期末（type = 0）
+    End,
+    // 期首（type = 1）。parse と同じく begin / start も受け付ける
+    #[serde(alias = "begin", alias = "start")]
+    Beginning,
+}
+
+impl PaymentTiming {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "beginning" | "begin" | "start" => Ok(PaymentTiming::Beginning),
+            "end" => Ok(PaymentTiming::End),
+            _ => Err(format!("積立のタイミングは beginning（月初）または end（月末）を指定してください: {}", value)),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaymentTiming::End => "月末",
+            PaymentTiming::Beginning => "月初",
+        }
+    }
+
+    // Excel の type の値
+    pub fn excel_type(&self) -> f64 {
+        match self {
+            PaymentTiming::End => 0.0,
+            PaymentTiming::Beginning => 1.0,
+        }
+    }
+}
+
+// これより絶対値の小さい利率は0とみなす（0に近い利率で割ると桁落ちして結果が不安定になる）
+const ZERO_RATE: f64 = 1e-12;
+
+// 年金の係数: (1 + rate × type) × [(1 + rate)^nper - 1] / rate（rate = 0 のときは nper）
+fn annuity_factor(rate: f64, nper: f64, timing: PaymentTiming) -> f64 {
+    if rate.abs() < ZERO_RATE {
+        return nper;
+    }
+    (1.0 + rate * timing.excel_type()) * ((1.0 + rate).powf(nper) - 1.0) / rate
+}
+
+// 将来価値（FV）
+#[allow(dead_code)]
+pub fn fv(rate: f64, nper: f64, pmt: f64, pv: f64, timing: PaymentTiming) -> f64 {
+    -(pv * (1.0 + rate).powf(nper) + pmt * annuity_factor(rate, nper, timing))
+}
+
+// 現在価値（PV）
+#[allow(dead_code)]
+pub fn pv(rate: f64, nper: f64, pmt: f64, fv: f64, timing: PaymentTiming) -> f64 {
+    -(fv + pmt * annuity_factor(rate, nper, timing)) / (1.0 + rate).powf(nper)
+}
+
+// 定期支払額（PMT）
+pub fn pmt(rate: f64, nper: f64, pv: f64, fv: f64, timing: PaymentTiming) -> f64 {
+    -(fv + pv * (1.0 + rate).powf(nper)) / annuity_factor(rate, nper, timing)
+}
+
+// 期数（NPER）。解がない場合（Excel の #NUM!）は None
+pub fn nper(rate: f64, pmt: f64, pv: f64, fv: f64, timing: PaymentTiming) -> Option<f64> {
+    let periods = if rate.abs() < ZERO_RATE {
+        -(pv + fv) / pmt
+    } else {
+        let z = pmt * (1.0 + rate * timing.excel_type()) / rate;
+        ((z - fv) / (pv + z)).ln() / (1.0 + rate).ln()
+    };
+    periods.is_finite().then_some(periods)
+}
+
+// 1期あたりの利率（RATE）。Excel と同じくニュートン法で guess から探し、収束しなければ None
+pub fn rate(nper: f64, pmt: f64, pv: f64, fv: f64, timing: PaymentTiming, guess: f64) -> Option<f64> {
+    let t = timing.excel_type();
+    let mut rate = guess;
+
+    for _ in 0..100 {
+        if rate <= -1.0 {
+            return None;
+        }
+        let (value, slope) = if rate.abs() < 1e-10 {
+            // rate = 0 の近くでは級数展開の1次までで近似する
+            (pv + pmt * nper + fv, pv * nper + pmt * nper * ((nper - 1.0) / 2.0 + t))
+        } else {
+            let growth = (1.0 + rate).powf(nper);
+            let growth_slope = nper * (1.0 + rate).powf(nper - 1.0);
+            let annuity = (growth - 1.0) / rate;
+            let annuity_slope = (growth_slope * rate - (growth - 1.0)) / (rate * rate);
+            (
+                pv * growth + pmt * (1.0 + rate * t) * annuity + fv,
+                pv * growth_slope + pmt * (t * annuity + (1.0 + rate * t) * annuity_slope),
+            )
+        };
+        if slope == 0.0 || !slope.is_finite() {
+            return None;
+        }
+        let next = rate - value / slope;
+        if (next - rate).abs() < 1e-12 {
+            return Some(next);
+        }
+        rate = next;
+    }
+    None
+}
+
+// ある期（per、1始まり）の支払額のうちの利息分（IPMT）
+#[allow(dead_code)]
+pub fn ipmt(rate: f64, per: f64, nper: f64, pv: f64, fv: f64, timing: PaymentTiming) -> f64 {
+    // 期首払いの1期目は、まだ利息が付いていない
+    if timing == PaymentTiming::Beginning && per == 1.0 {
+        return 0.0;
+    }
+    let payment = pmt(rate, nper, pv, fv, timing);
+    // 前の期の末の残高に付く利息
+    let interest = self::fv(rate, per - 1.0, payment, pv, timing) * rate;
+    match timing {
+        PaymentTiming::End => interest,
+        PaymentTiming::Beginning => interest / (1.0 + rate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Excel で計算した値との比較
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-6 * expected.abs().max(1.0),
+            "actual {} != expected {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn fv_matches_excel() {
+        // =FV(0.06/12, 10, -200, -500, 1)
+        assert_close(fv(0.06 / 12.0, 10.0, -200.0, -500.0, PaymentTiming::Beginning), 2581.403374060);
+        // =FV(0.12/12, 12, -1000)
+        assert_close(fv(0.12 / 12.0, 12.0, -1000.0, 0.0, PaymentTiming::End), 12682.503013197);
+        // =FV(0.11/12, 35, -2000, , 1)
+        assert_close(fv(0.11 / 12.0, 35.0, -2000.0, 0.0, PaymentTiming::Beginning), 82846.246371901);
+        // =FV(0, 12, -100, -1000)
+        assert_close(fv(0.0, 12.0, -100.0, -1000.0, PaymentTiming::End), 2200.0);
+        // 0に極めて近い利率でも利息なしと同じ
+        assert_close(fv(1e-15, 12.0, -100.0, -1000.0, PaymentTiming::Beginning), 2200.0);
+    }
+
+    #[test]
+    fn pv_matches_excel() {
+        // =PV(0.08/12, 12*20, 500, , 0)
+        assert_close(pv(0.08 / 12.0, 240.0, 500.0, 0.0, PaymentTiming::End), -59777.145851188);
+        // =PV(0.05/12, 120, -30000, 0, 1)
+        assert_close(pv(0.05 / 12.0, 120.0, -30000.0, 0.0, PaymentTiming::Beginning), 2840225.678638);
+    }
+
+    #[test]
+    fn pmt_matches_excel() {
+        // =PMT(0.08/12, 10, 10000)
+        assert_close(pmt(0.08 / 12.0, 10.0, 10000.0, 0.0, PaymentTiming::End), -1037.032089359);
+        // =PMT(0.08/12, 10, 10000, 0, 1)
+        assert_close(pmt(0.08 / 12.0, 10.0, 10000.0, 0.0, PaymentTiming::Beginning), -1030.164327178);
+        // =PMT(0.06/12, 18*12, 0, 50000)
+        assert_close(pmt(0.06 / 12.0, 216.0, 0.0, 50000.0, PaymentTiming::End), -129.081160868);
+        // =PMT(0, 10, 10000)
+        assert_close(pmt(0.0, 10.0, 10000.0, 0.0, PaymentTiming::End), -1000.0);
+    }
+
+    #[test]
+    fn nper_matches_excel() {
+        // =NPER(0.12/12, -100, -1000, 10000, 1)
+        assert_close(nper(0.01, -100.0, -1000.0, 10000.0, PaymentTiming::Beginning).unwrap(), 59.673865674);
+        // =NPER(0.12/12, -100, -1000, 10000)
+        assert_close(nper(0.01, -100.0, -1000.0, 10000.0, PaymentTiming::End).unwrap(), 60.082122854);
+        // =NPER(0.12/12, -100, -1000)
+        assert_close(nper(0.01, -100.0, -1000.0, 0.0, PaymentTiming::End).unwrap(), -9.578594040);
+        // 積み立てても届かない場合は #NUM!
+        assert_eq!(nper(0.01, 100.0, 1000.0, 10000.0, PaymentTiming::End), None);
+    }
+
+    #[test]
+    fn rate_matches_excel() {
+        // =RATE(4*12, -200, 8000)
+        assert_close(rate(48.0, -200.0, 8000.0, 0.0, PaymentTiming::End, 0.1).unwrap(), 0.007701472);
+        // =RATE(10, -1000, 0, 12000, 1)
+        assert_close(rate(10.0, -1000.0, 0.0, 12000.0, PaymentTiming::Beginning, 0.1).unwrap(), 0.032893897);
+        // 利息なしで釣り合う場合は 0
+        assert_close(rate(10.0, -100.0, 1000.0, 0.0, PaymentTiming::End, 0.1).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn ipmt_matches_excel() {
+        // =IPMT(0.1/12, 1, 3*12, 8000)
+        assert_close(ipmt(0.1 / 12.0, 1.0, 36.0, 8000.0, 0.0, PaymentTiming::End), -66.666666667);
+        // =IPMT(0.1, 3, 3, 8000)
+        assert_close(ipmt(0.1, 3.0, 3.0, 8000.0, 0.0, PaymentTiming::End), -292.447129909);
+        // =IPMT(0.1/12, 2, 36, 8000, 0, 1)
+        assert_close(ipmt(0.1 / 12.0, 2.0, 36.0, 8000.0, 0.0, PaymentTiming::Beginning), -64.533298918);
+        // 期首払いの1期目は利息なし
+        assert_close(ipmt(0.1 / 12.0, 1.0, 36.0, 8000.0, 0.0, PaymentTiming::Beginning), 0.0);
+    }
+}
@@ -0,0 +1,517 @@
+// 取り崩し期間: 積立期間の後、資産から毎月一定額を引き出す
+//
+// 毎月の順序は積立のタイミングに合わせる（月初なら 引き出し → 運用、月末なら 運用 → 引き出し）。
+// 残高が引き出し額に満たない月は残高をすべて引き出し、その月を資産が尽きた月とする。
+// 定額以外の取り崩しの方法では、毎年の期首にその年の引き出し額を決め、12ヶ月に分けて引き出す。
+//
+// 安全な引き出し率: 初年度に開始時の資産の一定割合を引き出し、以降はインフレ率に合わせて増やす
+// （4%ルールと同じ）とき、期間内に資産が尽きない最大の割合。引き出し額は率に比例するので、
+// 各月の引き出し額をそれまでの運用で割り引いた合計（現在価値）が開始時の資産と等しくなる率が上限になる。
+
+use serde::Deserialize;
+
+use crate::error::PlanError;
+use crate::simulation::grow_with_contribution;
+use crate::tvm::PaymentTiming;
+
+// 引き出し額に満たなかったとみなす不足の割合（浮動小数点の丸め誤差で資産が尽きたと判定しないため）
+const SHORTFALL_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone)]
+pub struct WithdrawalConfig {
+    // 毎月の引き出し額
+    pub monthly_withdrawal: f64,
+    // 取り崩す期間（年）
+    pub years: usize,
+    // 取り崩し期間の想定年利（None の場合は計画の想定年利を使う）
+    pub annual_rate: Option<f64>,
+    // 定額と比較する取り崩しの方法
+    pub strategies: Vec<WithdrawalStrategy>,
+    // 4%ルール・定率・ガードレールの（初年度の）引き出し率
+    pub initial_rate: f64,
+    // 安全な引き出し率を求めるときの成功率（資産が尽きない経路の割合）
+    pub success_rate: Option<f64>,
+}
+
+impl Default for WithdrawalConfig {
+    fn default() -> Self {
+        WithdrawalConfig {
+            monthly_withdrawal: 200_000.0,
+            years: 30,
+            annual_rate: None,
+            strategies: Vec::new(),
+            initial_rate: 0.04,
+            success_rate: None,
+        }
+    }
+}
+
+impl WithdrawalConfig {
+    pub fn validate(&self) -> Result<(), PlanError> {
+        if !self.monthly_withdrawal.is_finite() || self.monthly_withdrawal <= 0.0 {
+            return Err(PlanError::new("withdrawal.monthly", "毎月の引き出し額には正の金額を指定してください"));
+        }
+        if self.years == 0 || self.years > 100 {
+            return Err(PlanError::new("withdrawal.years", "取り崩し期間は1〜100年の範囲で指定してください"));
+        }
+        if let Some(rate) = self.annual_rate
+            && (!rate.is_finite() || rate <= -1.0)
+        {
+            return Err(PlanError::new("withdrawal.rate", "年利には-100%より大きい値を指定してください"));
+        }
+        if !(self.initial_rate > 0.0 && self.initial_rate < 1.0) {
+            return Err(PlanError::new("withdrawal.withdrawal_rate", "引き出し率は0%より大きく100%未満で指定してください"));
+        }
+        if let Some(rate) = self.success_rate
+            && !(rate > 0.0 && rate <= 1.0)
+        {
+            return Err(PlanError::new("withdrawal.success_rate", "成功率は0%より大きく100%以下で指定してください"));
+        }
+        Ok(())
+    }
+}
+
+pub struct WithdrawalResult {
+    // 各年末の残高（資産が尽きた後は0）
+    pub yearly_balance: Vec<f64>,
+    // 各年末までに引き出した金額の累計
+    pub yearly_withdrawn: Vec<f64>,
+    // 資産が尽きた月（1始まり）。期間内に尽きなければ None
+    pub depleted_month: Option<usize>,
+}
+
+// 1ヶ月分の引き出しと運用（積立額がマイナスになったものとみなす）。残高が足りなければ残りをすべて引き出す
+// 戻り値は (引き出し・運用後の残高, 実際に引き出した額)
+fn withdraw_month(balance: f64, withdrawal: f64, monthly_return: f64, timing: PaymentTiming) -> (f64, f64) {
+    let available = match timing {
+        PaymentTiming::Beginning => balance,
+        PaymentTiming::End => balance * (1.0 + monthly_return),
+    };
+    let amount = withdrawal.min(available.max(0.0));
+    (grow_with_contribution(balance, -amount, monthly_return, timing), amount)
+}
+
+// 月ごとのリターンを与えて、毎月一定額を引き出す
+pub fn simulate_withdrawals<I>(
+    starting_balance: f64,
+    monthly_withdrawal: f64,
+    monthly_returns: I,
+    timing: PaymentTiming,
+) -> WithdrawalResult
+where
+    I: IntoIterator<Item = f64>,
+{
+    let mut balance = starting_balance;
+    let mut withdrawn = 0.0;
+    let mut depleted_month = None;
+    let mut yearly_balance = Vec::new();
+    let mut yearly_withdrawn = Vec::new();
+
+    for (index, monthly_return) in monthly_returns.into_iter().enumerate() {
+        let month = index + 1;
+
+        let (next, amount) = withdraw_month(balance, monthly_withdrawal, monthly_return, timing);
+        balance = next;
+        withdrawn += amount;
+        if depleted_month.is_none() && amount < monthly_withdrawal * (1.0 - SHORTFALL_TOLERANCE) {
+            depleted_month = Some(month);
+        }
+
+        // 年末の残高を記録
+        if month % 12 == 0 {
+            yearly_balance.push(balance);
+            yearly_withdrawn.push(withdrawn);
+        }
+    }
+
+    WithdrawalResult {
+        yearly_balance,
+        yearly_withdrawn,
+        depleted_month,
+    }
+}
+
+// 年利固定で毎月一定額を引き出す
+pub fn simulate_fixed_withdrawals(
+    starting_balance: f64,
+    monthly_withdrawal: f64,
+    annual_rate: f64,
+    years: usize,
+    timing: PaymentTiming,
+) -> WithdrawalResult {
+    let monthly_rate = annual_rate / 12.0;
+    simulate_withdrawals(
+        starting_balance,
+        monthly_withdrawal,
+        std::iter::repeat_n(monthly_rate, years * 12),
+        timing,
+    )
+}
+
+// 取り崩しの方法（毎年、その年の引き出し額を決める）
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WithdrawalStrategy {
+    // 毎月一定額
+    Fixed,
+    // 初年度に資産の一定割合（4%ルール）を引き出し、以降はインフレ率に合わせて増やす
+    FourPercent,
+    // 毎年、その時点の資産の一定割合を引き出す
+    ConstantPercent,
+    // ガードレール（Guyton-Klinger）: インフレ調整を基本に、引き出し率が上下の閾値を超えたら10%増減する
+    Guardrails,
+    // VPW: 残り年数と想定年利から、資産を期間内に使い切る割合を毎年計算する
+    Vpw,
+}
+
+impl WithdrawalStrategy {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "fixed" => Ok(WithdrawalStrategy::Fixed),
+            "four-percent" | "4%" => Ok(WithdrawalStrategy::FourPercent),
+            "constant-percent" | "constant" => Ok(WithdrawalStrategy::ConstantPercent),
+            "guardrails" | "guyton-klinger" => Ok(WithdrawalStrategy::Guardrails),
+            "vpw" => Ok(WithdrawalStrategy::Vpw),
+            _ => Err(format!(
+                "取り崩しの方法は fixed / four-percent / constant-percent / guardrails / vpw のいずれかを指定してください: {}",
+                value
+            )),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WithdrawalStrategy::Fixed => "定額",
+            WithdrawalStrategy::FourPercent => "4%ルール(インフレ調整)",
+            WithdrawalStrategy::ConstantPercent => "定率",
+            WithdrawalStrategy::Guardrails => "ガードレール",
+            WithdrawalStrategy::Vpw => "VPW",
+        }
+    }
+
+    // その年の引き出し額（年額）
+    pub fn annual_withdrawal(&self, year: &WithdrawalYear, params: &StrategyParams) -> f64 {
+        match self {
+            WithdrawalStrategy::Fixed => params.monthly_withdrawal * 12.0,
+            WithdrawalStrategy::FourPercent => match year.previous_withdrawal {
+                None => year.starting_balance * params.initial_rate,
+                Some(previous) => previous * (1.0 + year.inflation),
+            },
+            WithdrawalStrategy::ConstantPercent => year.balance * params.initial_rate,
+            WithdrawalStrategy::Guardrails => {
+                let Some(previous) = year.previous_withdrawal else {
+                    return year.starting_balance * params.initial_rate;
+                };
+                if year.balance <= 0.0 {
+                    return previous;
+                }
+                // 前年のリターンがマイナスで引き出し率が初年度を上回る場合はインフレ調整をしない
+                let mut withdrawal = previous * (1.0 + year.inflation);
+                if year.previous_return < 0.0 && withdrawal / year.balance > params.initial_rate {
+                    withdrawal = previous;
+                }
+                let rate = withdrawal / year.balance;
+                if rate > params.initial_rate * 1.2 && year.remaining_years > 15 {
+                    // 資本保全ルール: 引き出し率が20%以上高くなったら10%減らす
+                    withdrawal *= 0.9;
+                } else if rate < params.initial_rate * 0.8 {
+                    // 繁栄ルール: 引き出し率が20%以上低くなったら10%増やす
+                    withdrawal *= 1.1;
+                }
+                withdrawal
+            }
+            WithdrawalStrategy::Vpw => {
+                // 残り年数で資産を使い切る年金額（期首払い）の割合
+                let n = year.remaining_years as f64;
+                let r = params.expected_return;
+                let rate = if r.abs() < 1e-9 {
+                    1.0 / n
+                } else {
+                    r / ((1.0 - (1.0 + r).powf(-n)) * (1.0 + r))
+                };
+                year.balance * rate
+            }
+        }
+    }
+}
+
+// 取り崩しの方法に共通のパラメータ
+#[derive(Debug, Clone)]
+pub struct StrategyParams {
+    pub monthly_withdrawal: f64,
+    // 4%ルール・定率・ガードレールの（初年度の）引き出し率
+    pub initial_rate: f64,
+    // VPWで使う想定年利
+    pub expected_return: f64,
+}
+
+// 引き出し額を決めるときに参照する、その年の期首の状況
+#[derive(Debug, Clone)]
+pub struct WithdrawalYear {
+    pub balance: f64,
+    pub starting_balance: f64,
+    // 前年の引き出し額（初年度は None）
+    pub previous_withdrawal: Option<f64>,
+    // 前年の運用リターン
+    pub previous_return: f64,
+    // その年のインフレ率
+    pub inflation: f64,
+    // この年を含めた残りの年数
+    pub remaining_years: usize,
+}
+
+pub struct StrategyResult {
+    // 各年末の残高
+    pub yearly_balance: Vec<f64>,
+    // 各年に実際に引き出した金額
+    pub yearly_withdrawal: Vec<f64>,
+    // 資産が尽きた月（1始まり）
+    pub depleted_month: Option<usize>,
+}
+
+impl StrategyResult {
+    pub fn total_withdrawn(&self) -> f64 {
+        self.yearly_withdrawal.iter().sum()
+    }
+
+    // 最も少なかった年の引き出し額
+    pub fn lowest_withdrawal(&self) -> f64 {
+        self.yearly_withdrawal.iter().copied().fold(f64::INFINITY, f64::min)
+    }
+
+    pub fn final_balance(&self) -> f64 {
+        *self.yearly_balance.last().unwrap_or(&0.0)
+    }
+}
+
+// 月ごとのリターンを与えて、取り崩しの方法に従って引き出す
+//   monthly_returns: 取り崩し期間の各月のリターン（12の倍数の長さ）
+//   inflation: 取り崩し期間の各年のインフレ率
+pub fn simulate_strategy(
+    starting_balance: f64,
+    strategy: WithdrawalStrategy,
+    params: &StrategyParams,
+    monthly_returns: &[f64],
+    inflation: &[f64],
+    timing: PaymentTiming,
+) -> StrategyResult {
+    let years = monthly_returns.len() / 12;
+    let mut balance = starting_balance;
+    let mut previous_withdrawal = None;
+    let mut previous_return = 0.0;
+    let mut depleted_month = None;
+    let mut yearly_balance = Vec::with_capacity(years);
+    let mut yearly_withdrawal = Vec::with_capacity(years);
+
+    for (year, returns) in monthly_returns.chunks(12).take(years).enumerate() {
+        let context = WithdrawalYear {
+            balance,
+            starting_balance,
+            previous_withdrawal,
+            previous_return,
+            inflation: inflation.get(year).copied().unwrap_or(0.0),
+            remaining_years: years - year,
+        };
+        let annual = strategy.annual_withdrawal(&context, params).max(0.0);
+        let monthly = annual / 12.0;
+
+        let mut withdrawn = 0.0;
+        let mut growth = 1.0;
+        for (index, monthly_return) in returns.iter().enumerate() {
+            let (next, amount) = withdraw_month(balance, monthly, *monthly_return, timing);
+            balance = next;
+            withdrawn += amount;
+            if depleted_month.is_none() && amount < monthly * (1.0 - SHORTFALL_TOLERANCE) {
+                depleted_month = Some(year * 12 + index + 1);
+            }
+            growth *= 1.0 + monthly_return;
+        }
+
+        yearly_balance.push(balance);
+        yearly_withdrawal.push(withdrawn);
+        previous_withdrawal = Some(annual);
+        previous_return = growth - 1.0;
+    }
+
+    StrategyResult {
+        yearly_balance,
+        yearly_withdrawal,
+        depleted_month,
+    }
+}
+
+// 月ごとのリターンに対して、資産が尽きない最大の引き出し率（初年度の年額 ÷ 開始時の資産）
+//   monthly_returns: 取り崩し期間の各月のリターン
+//   inflation: 取り崩し期間の各年のインフレ率（1年目の値は使わない）
+pub fn safe_withdrawal_rate(monthly_returns: &[f64], inflation: &[f64], timing: PaymentTiming) -> f64 {
+    // 初年度の年額を1としたときの各月の引き出し額を、開始時点まで割り引いて合計する
+    let mut level = 1.0;
+    let mut growth = 1.0;
+    let mut present_value = 0.0;
+
+    for (year, returns) in monthly_returns.chunks(12).enumerate() {
+        if year > 0 {
+            level *= 1.0 + inflation.get(year).copied().unwrap_or(0.0);
+        }
+        for monthly_return in returns {
+            // 月初の引き出しはその月の運用前、月末の引き出しは運用後の時点から割り引く
+            if timing == PaymentTiming::Beginning {
+                present_value += level / 12.0 / growth;
+            }
+            growth *= 1.0 + monthly_return;
+            // 資産がなくなるほど下落した場合は、どの率でも尽きる
+            if growth <= 0.0 {
+                return 0.0;
+            }
+            if timing == PaymentTiming::End {
+                present_value += level / 12.0 / growth;
+            }
+        }
+    }
+
+    if present_value > 0.0 { 1.0 / present_value } else { 0.0 }
+}
+
+// 経路（開始月）ごとの安全な引き出し率から、success_rate の割合以上で資産が尽きない最大の引き出し率
+pub fn rate_for_success(safe_rates: &[f64], success_rate: f64) -> f64 {
+    if safe_rates.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = safe_rates.to_vec();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let count = ((success_rate * sorted.len() as f64 - 1e-9).ceil() as usize).clamp(1, sorted.len());
+    sorted[count - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn four_percent(initial_rate: f64, monthly_returns: &[f64], inflation: &[f64], timing: PaymentTiming) -> StrategyResult {
+        let params = StrategyParams {
+            monthly_withdrawal: 0.0,
+            initial_rate,
+            expected_return: 0.0,
+        };
+        simulate_strategy(10_000_000.0, WithdrawalStrategy::FourPercent, &params, monthly_returns, inflation, timing)
+    }
+
+    #[test]
+    fn safe_withdrawal_rate_without_returns() {
+        // リターンもインフレもなければ、25年間で使い切る率は 1 / 25
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            assert!((safe_withdrawal_rate(&[0.0; 300], &[0.0; 25], timing) - 0.04).abs() < 1e-12);
+            // 資産がなくなるほどの下落があれば0
+            assert_eq!(safe_withdrawal_rate(&[0.01, -1.0, 0.01], &[0.0], timing), 0.0);
+        }
+    }
+
+    // 安全な引き出し率で4%ルールの取り崩しをすると、ちょうど期間の最後に資産を使い切ること
+    #[test]
+    fn safe_withdrawal_rate_is_the_depletion_boundary() {
+        let monthly_returns: Vec<f64> = (0..360).map(|month| if month % 12 < 9 { 0.008 } else { -0.01 }).collect();
+        let inflation: Vec<f64> = (0..30).map(|year| 0.01 + 0.001 * year as f64).collect();
+        for timing in [PaymentTiming::Beginning, PaymentTiming::End] {
+            let rate = safe_withdrawal_rate(&monthly_returns, &inflation, timing);
+
+            let result = four_percent(rate, &monthly_returns, &inflation, timing);
+            assert!(result.final_balance().abs() < 1e-3, "{:?}: {}", timing, result.final_balance());
+            assert_eq!(four_percent(rate * 0.999, &monthly_returns, &inflation, timing).depleted_month, None);
+            assert!(four_percent(rate * 1.001, &monthly_returns, &inflation, timing).depleted_month.is_some());
+        }
+    }
+
+    // 資産が尽きる月と引き出した総額が、積立のタイミングに合わせた手計算の値と一致すること
+    //   月初: 19.7万 × 1.01 = 19.897万 → 9.897万 × 1.01 = 9.99597万 < 10万 なので3ヶ月目に尽きる
+    //   月末: 29.997万 - 10万 = 19.997万 → 20.19697万 - 10万 = 10.19697万 → 10.2989397万 - 10万 = 0.2989397万
+    //         → 4ヶ月目は 0.2989397万 × 1.01 しか引き出せない
+    #[test]
+    fn depletion_month_follows_the_timing() {
+        let cases = [
+            (PaymentTiming::Beginning, 3, 100_000.0 * 2.0 + 99_959.7),
+            (PaymentTiming::End, 4, 100_000.0 * 3.0 + 2_989.397 * 1.01),
+        ];
+        let returns = [0.01; 12];
+        for (timing, month, withdrawn) in cases {
+            let fixed = simulate_fixed_withdrawals(297_000.0, 100_000.0, 0.12, 1, timing);
+            assert_eq!(fixed.depleted_month, Some(month), "{:?}", timing);
+            assert!((fixed.yearly_withdrawn[0] - withdrawn).abs() < 1e-6, "{:?}: {}", timing, fixed.yearly_withdrawn[0]);
+            assert!(fixed.yearly_balance[0].abs() < 1e-9);
+
+            // 取り崩しの方法の定額も同じ月に尽きる
+            let params = StrategyParams {
+                monthly_withdrawal: 100_000.0,
+                initial_rate: 0.04,
+                expected_return: 0.0,
+            };
+            let strategy = simulate_strategy(297_000.0, WithdrawalStrategy::Fixed, &params, &returns, &[0.0], timing);
+            assert_eq!(strategy.depleted_month, Some(month), "{:?}", timing);
+            assert!((strategy.total_withdrawn() - withdrawn).abs() < 1e-6);
+        }
+    }
+
+    fn params(initial_rate: f64, expected_return: f64) -> StrategyParams {
+        StrategyParams {
+            monthly_withdrawal: 0.0,
+            initial_rate,
+            expected_return,
+        }
+    }
+
+    fn year(balance: f64, previous_withdrawal: Option<f64>, previous_return: f64, remaining_years: usize) -> WithdrawalYear {
+        WithdrawalYear {
+            balance,
+            starting_balance: 10_000_000.0,
+            previous_withdrawal,
+            previous_return,
+            inflation: 0.02,
+            remaining_years,
+        }
+    }
+
+    #[test]
+    fn guardrails_adjust_the_withdrawal() {
+        let guardrails = |year: &WithdrawalYear| WithdrawalStrategy::Guardrails.annual_withdrawal(year, &params(0.04, 0.0));
+        // 初年度は開始時の資産の4%
+        assert!((guardrails(&year(10_000_000.0, None, 0.0, 30)) - 400_000.0).abs() < 1e-6);
+        // 引き出し率が閾値の内側ならインフレ調整のみ
+        assert!((guardrails(&year(10_000_000.0, Some(400_000.0), 0.05, 29)) - 408_000.0).abs() < 1e-6);
+        // 資本保全ルール: 408,000 / 500万 = 8.16% > 4.8% なので10%減らす
+        assert!((guardrails(&year(5_000_000.0, Some(400_000.0), 0.05, 20)) - 367_200.0).abs() < 1e-6);
+        // 残り15年以下では資本保全ルールを適用しない
+        assert!((guardrails(&year(5_000_000.0, Some(400_000.0), 0.05, 15)) - 408_000.0).abs() < 1e-6);
+        // 繁栄ルール: 408,000 / 2,000万 = 2.04% < 3.2% なので10%増やす
+        assert!((guardrails(&year(20_000_000.0, Some(400_000.0), 0.05, 20)) - 448_800.0).abs() < 1e-6);
+        // 前年がマイナスで引き出し率が初年度を上回るときはインフレ調整をしない
+        assert!((guardrails(&year(9_000_000.0, Some(400_000.0), -0.1, 20)) - 400_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn vpw_and_constant_percent_withdrawals() {
+        let vpw = |expected_return: f64, remaining_years: usize| {
+            WithdrawalStrategy::Vpw.annual_withdrawal(&year(6_000_000.0, Some(0.0), 0.0, remaining_years), &params(0.04, expected_return))
+        };
+        // 利回り0%なら残り年数で等分し、最後の年は残りをすべて引き出す
+        assert!((vpw(0.0, 30) - 200_000.0).abs() < 1e-6);
+        assert!((vpw(0.05, 1) - 6_000_000.0).abs() < 1e-6);
+        // 期首払いの年金額: 600万 × 0.05 / ((1 - 1.05^-30) × 1.05)
+        assert!((vpw(0.05, 30) - 371_722.5).abs() < 0.1, "{}", vpw(0.05, 30));
+
+        let constant = WithdrawalStrategy::ConstantPercent.annual_withdrawal(&year(6_000_000.0, Some(500_000.0), 0.0, 30), &params(0.04, 0.0));
+        assert!((constant - 240_000.0).abs() < 1e-6);
+    }
+
+    // 利回り0%のVPWは期間の最後にちょうど使い切り、資産が尽きたとは判定しないこと
+    #[test]
+    fn vpw_at_zero_return_spends_exactly_to_the_end() {
+        let result = simulate_strategy(10_000_000.0, WithdrawalStrategy::Vpw, &params(0.04, 0.0), &[0.0; 360], &[0.0; 30], PaymentTiming::Beginning);
+        assert_eq!(result.depleted_month, None);
+        assert!(result.final_balance().abs() < 1e-3, "{}", result.final_balance());
+        assert!(result.yearly_withdrawal.iter().all(|w| (w - 10_000_000.0 / 30.0).abs() < 1e-3));
+
+        // 定率は残高の一定割合なので尽きることはなく、毎年4%ずつ減る
+        let result = simulate_strategy(10_000_000.0, WithdrawalStrategy::ConstantPercent, &params(0.04, 0.0), &[0.0; 360], &[0.0; 30], PaymentTiming::Beginning);
+        assert_eq!(result.depleted_month, None);
+        assert!((result.final_balance() - 10_000_000.0 * 0.96f64.powi(30)).abs() < 1e-3);
+    }
+}